@@ -17,7 +17,7 @@ fn main() {
 // | /                /
 // three-------------/
 fn exec(execstate: &mut ExecututionState) -> u32 {
-    let mut store = &mut execstate.store;
+    let store = &mut execstate.store;
     let runnable = &mut execstate.runnable.runnable;
     let mut run_count = 0;
 
@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{FunctionDefinition, ParseReport};
+
+/// Finds the functions in a whole crate, starting from its root file
+/// (`lib.rs` or `main.rs`).  Follows `mod foo;` declarations to their files
+/// and descends into inline modules, setting each function's
+/// `module_path`.  Associated functions in inherent `impl` blocks are
+/// included under the type's name, but methods and `#[cfg(test)]` modules
+/// are skipped.
+///
+/// With an `attribute`, only functions carrying it are kept, matched on the
+/// last path segment so `node` finds `#[ive_macros::node]` too.
+pub fn scan_crate(
+    root: impl AsRef<Path>,
+    attribute: Option<&str>,
+) -> Result<Vec<FunctionDefinition>> {
+    Ok(scan_crate_report(root, attribute)?.functions)
+}
+
+/// Like `scan_crate`, also listing the functions that were skipped and
+/// why.  Methods are only listed when they carry `attribute`, as they were
+/// clearly meant to be found.
+pub fn scan_crate_report(root: impl AsRef<Path>, attribute: Option<&str>) -> Result<ParseReport> {
+    let root = root.as_ref();
+    let dir = root.parent().unwrap_or_else(|| Path::new(""));
+    let mut scanner = Scanner {
+        attribute,
+        report: ParseReport::default(),
+    };
+    scanner.scan_file(root, dir, &["crate".to_string()])?;
+    Ok(scanner.report)
+}
+
+struct Scanner<'a> {
+    attribute: Option<&'a str>,
+    report: ParseReport,
+}
+
+impl Scanner<'_> {
+    /// Scans a module's file.  `child_dir` is where its own `mod foo;`
+    /// declarations are looked for.
+    fn scan_file(&mut self, path: &Path, child_dir: &Path, module: &[String]) -> Result<()> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+        let file =
+            syn::parse_file(&content).with_context(|| format!("Parsing {}", path.display()))?;
+        self.scan_items(&file.items, path, child_dir, module)
+    }
+
+    fn scan_items(
+        &mut self,
+        items: &[syn::Item],
+        file: &Path,
+        child_dir: &Path,
+        module: &[String],
+    ) -> Result<()> {
+        for item in items {
+            match item {
+                syn::Item::Fn(f) => self.push(f, file, module)?,
+                syn::Item::Impl(imp) if imp.trait_.is_none() => {
+                    let Some(type_name) = type_name(&imp.self_ty) else {
+                        continue;
+                    };
+                    let module = [module, &[type_name]].concat();
+                    for item in imp.items.iter() {
+                        let syn::ImplItem::Fn(method) = item else {
+                            continue;
+                        };
+                        let wanted = self
+                            .attribute
+                            .is_some_and(|a| has_attribute(&method.attrs, a));
+                        if method.sig.receiver().is_some() && !wanted {
+                            continue;
+                        }
+                        let f = syn::ItemFn {
+                            attrs: method.attrs.clone(),
+                            vis: method.vis.clone(),
+                            sig: method.sig.clone(),
+                            block: Box::new(method.block.clone()),
+                        };
+                        self.push(&f, file, &module)?;
+                    }
+                }
+                syn::Item::Mod(m) if is_cfg_test(&m.attrs) => {}
+                syn::Item::Mod(m) => {
+                    let name = m.ident.to_string();
+                    let sub_module = [module, std::slice::from_ref(&name)].concat();
+                    match &m.content {
+                        Some((_, items)) => {
+                            self.scan_items(items, file, &child_dir.join(&name), &sub_module)?;
+                        }
+                        None => {
+                            let (path, dir) = module_file(file, child_dir, &name, &m.attrs)?;
+                            self.scan_file(&path, &dir, &sub_module)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, f: &syn::ItemFn, file: &Path, module: &[String]) -> Result<()> {
+        if let Some(attribute) = self.attribute {
+            if !has_attribute(&f.attrs, attribute) {
+                return Ok(());
+            }
+        }
+        let count = self.report.functions.len();
+        self.report.add(f, Some(&file.display().to_string()))?;
+        if let Some(definition) = self.report.functions.get_mut(count) {
+            definition.module_path = module.join("::");
+        }
+        Ok(())
+    }
+}
+
+/// Whether there's an attribute with `name` as its last path segment.
+fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .any(|a| a.path().segments.last().is_some_and(|s| s.ident == name))
+}
+
+/// The file for `mod name;` and the directory its own modules are in.
+fn module_file(
+    file: &Path,
+    child_dir: &Path,
+    name: &str,
+    attrs: &[syn::Attribute],
+) -> Result<(PathBuf, PathBuf)> {
+    if let Some(path) = path_attribute(attrs) {
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+        let path = dir.join(path);
+        let child_dir = match path.file_name().and_then(|f| f.to_str()) {
+            Some("mod.rs") => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            _ => path.with_extension(""),
+        };
+        return Ok((path, child_dir));
+    }
+
+    let flat = child_dir.join(format!("{}.rs", name));
+    let nested = child_dir.join(name).join("mod.rs");
+    let path = [flat, nested]
+        .into_iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| anyhow!("Could not find module {} in {}", name, child_dir.display()))?;
+    Ok((path, child_dir.join(name)))
+}
+
+/// The `x` in `#[path = "x"]`
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .find(|a| a.path().is_ident("path"))
+        .and_then(|a| match &a.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }),
+                ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+}
+
+fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|a| {
+        a.path().is_ident("cfg")
+            && match &a.meta {
+                syn::Meta::List(list) => list.tokens.to_string() == "test",
+                _ => false,
+            }
+    })
+}
+
+/// The name of the type an `impl` block is for, without generics.
+fn type_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/scan/src/lib.rs"
+    );
+
+    #[test]
+    fn test_scan_crate() {
+        let functions = scan_crate(FIXTURE, None).unwrap();
+        let paths = functions.iter().map(|f| f.path()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "crate::math::ops::negate",
+                "crate::math::add",
+                "crate::text::helpers::whisper",
+                "crate::text::shout",
+                "crate::one",
+                "crate::inline::not_a_node",
+                "crate::inline::deeper::deep",
+                "crate::Counter::start",
+            ]
+        );
+
+        let negate = &functions[0];
+        assert!(negate.span.file.as_ref().unwrap().ends_with("math/ops.rs"));
+        assert_eq!(negate.span.line, 2);
+    }
+
+    #[test]
+    fn test_scan_with_attribute() {
+        let functions = scan_crate(FIXTURE, Some("make_dynamicable")).unwrap();
+        let paths = functions.iter().map(|f| f.path()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "crate::math::ops::negate",
+                "crate::math::add",
+                "crate::one",
+                "crate::inline::deeper::deep",
+                "crate::Counter::start",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_marked_methods() {
+        let report = scan_crate_report(FIXTURE, Some("make_dynamicable")).unwrap();
+        assert_eq!(report.functions.len(), 5);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].name, "reset");
+        assert_eq!(report.skipped[0].reason, crate::SkipReason::Receiver);
+        assert!(report.skipped[0]
+            .span
+            .file
+            .as_ref()
+            .unwrap()
+            .ends_with("lib.rs"));
+
+        // Without looking for the attribute, methods aren't expected
+        let report = scan_crate_report(FIXTURE, None).unwrap();
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_missing_module() {
+        let dir = std::env::temp_dir().join(format!("fn_parser_scan_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let root = dir.join("lib.rs");
+        std::fs::write(&root, "mod missing;").unwrap();
+        let err = scan_crate(&root, None).unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(err.starts_with("Could not find module missing"), "{}", err);
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Result};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
+
+use crate::{function_to_function_definition, FunctionDefinition, SourceSpan};
+
+/// Why a function couldn't be turned into a `FunctionDefinition`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// Methods taking `self` have no free function to call
+    Receiver,
+    /// Arguments need a plain name, not a pattern like `(a, b): (i32, i32)`
+    Pattern(String),
+    /// A type that can't be named on a port, like `impl Trait`
+    UnsupportedType(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SkipReason::Receiver => write!(f, "takes self"),
+            SkipReason::Pattern(pat) => write!(f, "argument pattern `{}` isn't a plain name", pat),
+            SkipReason::UnsupportedType(ty) => write!(f, "type `{}` isn't supported", ty),
+        }
+    }
+}
+
+/// A function that was left out, and where the problem is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedItem {
+    pub name: String,
+    /// Whether the function is `pub`, so meant to be part of a library
+    #[serde(default)]
+    pub is_pub: bool,
+    pub span: SourceSpan,
+    pub reason: SkipReason,
+}
+
+impl std::fmt::Display for SkippedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.span.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(
+            f,
+            "{}:{}: skipped {}: {}",
+            self.span.line, self.span.column, self.name, self.reason
+        )
+    }
+}
+
+impl std::error::Error for SkippedItem {}
+
+/// The functions parsed from some source, along with the ones that had to
+/// be skipped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub functions: Vec<FunctionDefinition>,
+    pub skipped: Vec<SkippedItem>,
+}
+
+impl ParseReport {
+    /// Adds a function, or records why it was skipped.  `file` fills in the
+    /// spans when known.
+    pub fn add(&mut self, f: &syn::ItemFn, file: Option<&str>) -> Result<()> {
+        if let Err(mut skipped) = check_function(f) {
+            skipped.span.file = file.map(str::to_string);
+            self.skipped.push(skipped);
+            return Ok(());
+        }
+        let mut definition = function_to_function_definition(f)?;
+        definition.span.file = file.map(str::to_string);
+        self.functions.push(definition);
+        Ok(())
+    }
+
+    /// Fails listing every skipped function, if there are any.
+    pub fn ensure_complete(&self) -> Result<()> {
+        if self.skipped.is_empty() {
+            return Ok(());
+        }
+        let lines = self
+            .skipped
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        bail!(
+            "{} function(s) couldn't be parsed:\n{}",
+            self.skipped.len(),
+            lines.join("\n")
+        )
+    }
+}
+
+/// Checks a function can be described, pointing at the first thing that
+/// can't.
+pub fn check_function(f: &syn::ItemFn) -> std::result::Result<(), SkippedItem> {
+    let skip = |span: proc_macro2::Span, reason: SkipReason| {
+        let start = span.start();
+        Err(SkippedItem {
+            name: f.sig.ident.to_string(),
+            is_pub: matches!(f.vis, syn::Visibility::Public(_)),
+            span: SourceSpan {
+                file: None,
+                line: start.line,
+                column: start.column,
+            },
+            reason,
+        })
+    };
+
+    for input in f.sig.inputs.iter() {
+        let typed = match input {
+            syn::FnArg::Receiver(receiver) => return skip(receiver.span(), SkipReason::Receiver),
+            syn::FnArg::Typed(typed) => typed,
+        };
+        if !matches!(&*typed.pat, syn::Pat::Ident(p) if p.subpat.is_none()) {
+            let pat = typed.pat.to_token_stream().to_string();
+            return skip(typed.pat.span(), SkipReason::Pattern(pat));
+        }
+        if let Some(ty) = unsupported_type(&typed.ty) {
+            let name = ty.to_token_stream().to_string();
+            return skip(ty.span(), SkipReason::UnsupportedType(name));
+        }
+    }
+    if let syn::ReturnType::Type(_, ty) = &f.sig.output {
+        if let Some(ty) = unsupported_type(ty) {
+            let name = ty.to_token_stream().to_string();
+            return skip(ty.span(), SkipReason::UnsupportedType(name));
+        }
+    }
+    Ok(())
+}
+
+/// The part of a type that has no name a port could carry, looking through
+/// references, slices and generic arguments.
+fn unsupported_type(ty: &syn::Type) -> Option<&syn::Type> {
+    match ty {
+        syn::Type::ImplTrait(_)
+        | syn::Type::Infer(_)
+        | syn::Type::Macro(_)
+        | syn::Type::Never(_)
+        | syn::Type::Verbatim(_) => Some(ty),
+        syn::Type::Reference(r) => unsupported_type(&r.elem),
+        syn::Type::Slice(s) => unsupported_type(&s.elem),
+        syn::Type::Array(a) => unsupported_type(&a.elem),
+        syn::Type::Paren(p) => unsupported_type(&p.elem),
+        syn::Type::Group(g) => unsupported_type(&g.elem),
+        syn::Type::Tuple(t) => t.elems.iter().find_map(unsupported_type),
+        syn::Type::Path(p) => p
+            .path
+            .segments
+            .iter()
+            .filter_map(|s| match &s.arguments {
+                syn::PathArguments::AngleBracketed(args) => Some(args),
+                _ => None,
+            })
+            .flat_map(|args| args.args.iter())
+            .find_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => unsupported_type(ty),
+                _ => None,
+            }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> std::result::Result<(), SkippedItem> {
+        check_function(&syn::parse_str::<syn::ItemFn>(source).unwrap())
+    }
+
+    #[test]
+    fn test_reasons() {
+        assert_eq!(
+            check("fn f(a: i32, b: &[Vec<u8>]) -> Option<i32> {}"),
+            Ok(())
+        );
+        assert_eq!(check("fn f(mut a: i32, b: &dyn Fn()) {}"), Ok(()));
+
+        let skipped = check("fn f(&self) {}").unwrap_err();
+        assert_eq!(skipped.reason, SkipReason::Receiver);
+        assert_eq!(skipped.name, "f");
+
+        let skipped = check("fn f((a, b): (i32, i32)) {}").unwrap_err();
+        assert_eq!(skipped.reason, SkipReason::Pattern("(a , b)".to_string()));
+
+        let skipped = check("fn f(a: Vec<impl Clone>) {}").unwrap_err();
+        assert_eq!(
+            skipped.reason,
+            SkipReason::UnsupportedType("impl Clone".to_string())
+        );
+
+        assert_eq!(check("fn f() -> Box<dyn Fn()> {}"), Ok(()));
+        let skipped = check("fn f() -> impl Iterator<Item = i32> {}").unwrap_err();
+        assert_eq!(
+            skipped.reason,
+            SkipReason::UnsupportedType("impl Iterator < Item = i32 >".to_string())
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let source = "\nfn good() {}\nfn bad(&self) {}";
+        let file = syn::parse_file(source).unwrap();
+        let mut report = ParseReport::default();
+        for item in file.items.iter() {
+            if let syn::Item::Fn(f) = item {
+                report.add(f, Some("src/lib.rs")).unwrap();
+            }
+        }
+        assert_eq!(report.functions.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(
+            report.skipped[0].to_string(),
+            "src/lib.rs:3:7: skipped bad: takes self"
+        );
+        assert_eq!(
+            report.ensure_complete().unwrap_err().to_string(),
+            "1 function(s) couldn't be parsed:\nsrc/lib.rs:3:7: skipped bad: takes self"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::output::FileFunctions;
+use crate::FunctionDefinition;
+
+/// How the functions differ between two parses.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedFunction>,
+}
+
+/// A function whose signature changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFunction {
+    pub path: String,
+    pub old: String,
+    pub new: String,
+}
+
+impl FunctionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two sets of functions, matched by their path.  Only
+/// signatures are compared, so doc and body changes don't show up.
+pub fn diff_functions(old: &[FunctionDefinition], new: &[FunctionDefinition]) -> FunctionDiff {
+    let old = old.iter().map(|f| (f.path(), f)).collect::<Vec<_>>();
+    let new = new.iter().map(|f| (f.path(), f)).collect::<Vec<_>>();
+    diff_keyed(&old, &new)
+}
+
+/// Compares the functions in two sets of files, matched by their file and
+/// path.  Files are named relative to the directory all the files in their
+/// set are in, so `old/x.rs` lines up with `new/x.rs`, and a set of one
+/// file is named by its file name.  Functions are listed as `x.rs: name`.
+/// Two single files are matched by path alone, whatever they're called.
+pub fn diff_files(old: &[FileFunctions], new: &[FileFunctions]) -> FunctionDiff {
+    let with_file = old.len() != 1 || new.len() != 1;
+    diff_keyed(&file_keys(old, with_file), &file_keys(new, with_file))
+}
+
+fn file_keys(files: &[FileFunctions], with_file: bool) -> Vec<(String, &FunctionDefinition)> {
+    let dirs = files
+        .iter()
+        .map(|f| Path::new(&f.path).parent().unwrap_or(Path::new("")))
+        .collect::<Vec<_>>();
+    // The deepest directory every file is in
+    let common = dirs.first().map(|first| {
+        first
+            .ancestors()
+            .find(|dir| dirs.iter().all(|d| d.starts_with(dir)))
+            .unwrap_or(Path::new(""))
+    });
+
+    let mut keys = vec![];
+    for file in files {
+        let path = Path::new(&file.path);
+        let relative = common
+            .and_then(|common| path.strip_prefix(common).ok())
+            .unwrap_or(path);
+        for f in file.functions.iter() {
+            let key = match with_file {
+                true => format!("{}: {}", relative.display(), f.path()),
+                false => f.path(),
+            };
+            keys.push((key, f));
+        }
+    }
+    keys
+}
+
+fn diff_keyed(
+    old: &[(String, &FunctionDefinition)],
+    new: &[(String, &FunctionDefinition)],
+) -> FunctionDiff {
+    let mut diff = FunctionDiff::default();
+    for (key, o) in old {
+        match new.iter().find(|(k, _)| k == key) {
+            None => diff.removed.push(key.clone()),
+            Some((_, n)) if n.signature() != o.signature() => diff.changed.push(ChangedFunction {
+                path: key.clone(),
+                old: o.signature(),
+                new: n.signature(),
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.added = new
+        .iter()
+        .filter(|(key, _)| !old.iter().any(|(k, _)| k == key))
+        .map(|(key, _)| key.clone())
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_parsing::parse_file_str;
+
+    fn parse(source: &str) -> Vec<FunctionDefinition> {
+        parse_file_str(source).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = parse("fn a(x: u32) {} fn b() {} fn c() -> u8 { 0 }");
+        let new = parse("fn a(y: u32) {} fn c() -> u8 { 1 } fn d() {}");
+        let diff = diff_functions(&old, &new);
+        assert_eq!(diff.added, vec!["d"]);
+        assert_eq!(diff.removed, vec!["b"]);
+        assert_eq!(
+            diff.changed,
+            vec![ChangedFunction {
+                path: "a".to_string(),
+                old: "fn a(x: u32)".to_string(),
+                new: "fn a(y: u32)".to_string(),
+            }]
+        );
+        assert!(diff_functions(&old, &old).is_empty());
+    }
+
+    fn file(path: &str, source: &str) -> FileFunctions {
+        FileFunctions {
+            path: path.to_string(),
+            functions: parse(source),
+            skipped: vec![],
+        }
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let old = vec![
+            file("old/x.rs", "pub fn new() -> u8 { 0 }"),
+            file("old/y.rs", "pub fn new() -> u32 { 0 }"),
+        ];
+        let new = vec![
+            file("new/x.rs", "pub fn new() -> u8 { 0 }"),
+            file("new/y.rs", "pub fn new() -> u32 { 0 }"),
+        ];
+        assert!(diff_files(&old, &new).is_empty());
+
+        let moved = vec![
+            file("new/x.rs", "pub fn new() -> u8 { 0 }"),
+            file("new/sub/y.rs", "pub fn new() -> u64 { 0 }"),
+        ];
+        let diff = diff_files(&old, &moved);
+        assert_eq!(diff.removed, vec!["y.rs: new"]);
+        assert_eq!(diff.added, vec!["sub/y.rs: new"]);
+
+        // Single files are compared whatever they're called
+        let one = vec![file("a.rs", "fn f() -> u8 { 0 }")];
+        let other = vec![file("b.rs", "fn f() -> u16 { 0 }")];
+        assert_eq!(diff_files(&one, &other).changed[0].path, "f");
+
+        // A single file lines up with the same file in a directory
+        let one = vec![file("old/x.rs", "pub fn new() -> u8 { 0 }")];
+        let diff = diff_files(&one, &new);
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.added, vec!["y.rs: new"]);
+        assert_eq!(diff_files(&new, &one).removed, vec!["y.rs: new"]);
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+/// A `///` doc comment split into the parts a node description needs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Documentation {
+    /// The first paragraph
+    pub summary: String,
+    /// Everything after the summary except the `# Arguments` section
+    pub description: String,
+    /// The entries of the `# Arguments` section
+    pub arguments: Vec<ArgumentDoc>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArgumentDoc {
+    pub name: String,
+    pub description: String,
+}
+
+impl Documentation {
+    /// The documentation for the argument called `name`, if any.
+    pub fn argument(&self, name: &str) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.description.as_str())
+    }
+}
+
+/// The lines of the `#[doc = "..."]` attributes, which is what `///`
+/// comments become.
+pub fn doc_lines(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        // `split` rather than `lines` so a blank `///` stays a blank line
+        .flat_map(|s| s.split('\n').map(str::to_string).collect::<Vec<_>>())
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Splits doc comment lines into a summary, description and arguments.
+///
+/// Arguments are taken from a `# Arguments` section with one bullet per
+/// argument, in the rustdoc style of ``* `name` - what it is``.
+pub fn parse_docs(lines: &[String]) -> Documentation {
+    let mut docs = Documentation::default();
+    let mut summary = vec![];
+    let mut description = vec![];
+    let mut in_summary = true;
+    let mut in_arguments = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if in_summary {
+            if trimmed.is_empty() {
+                in_summary = summary.is_empty();
+            } else if trimmed.starts_with('#') {
+                in_summary = false;
+            } else {
+                summary.push(trimmed);
+                continue;
+            }
+        }
+        if let Some(heading) = trimmed.strip_prefix('#') {
+            in_arguments = heading.trim_start_matches('#').trim() == "Arguments";
+            if in_arguments {
+                continue;
+            }
+        }
+        if !in_arguments {
+            description.push(line.as_str());
+            continue;
+        }
+
+        match parse_argument(trimmed) {
+            Some(argument) => docs.arguments.push(argument),
+            // A continuation of the last argument
+            None if !trimmed.is_empty() => {
+                if let Some(last) = docs.arguments.last_mut() {
+                    last.description.push(' ');
+                    last.description.push_str(trimmed);
+                }
+            }
+            None => {}
+        }
+    }
+
+    docs.summary = summary.join(" ");
+    docs.description = description.join("\n").trim().to_string();
+    docs
+}
+
+/// Parses ``* `name` - description``, also allowing `-` bullets and `:`
+/// after the name.
+fn parse_argument(line: &str) -> Option<ArgumentDoc> {
+    let rest = line
+        .strip_prefix('*')
+        .or_else(|| line.strip_prefix('-'))?
+        .trim_start();
+    let rest = rest.strip_prefix('`')?;
+    let (name, rest) = rest.split_once('`')?;
+    let rest = rest.trim_start();
+    let rest = rest
+        .strip_prefix('-')
+        .or_else(|| rest.strip_prefix(':'))
+        .unwrap_or(rest);
+    Some(ArgumentDoc {
+        name: name.to_string(),
+        description: rest.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_sections() {
+        let docs = parse_docs(&lines(
+            "Adds two numbers.\n\
+             \n\
+             Wraps on overflow.\n\
+             \n\
+             # Arguments\n\
+             \n\
+             * `a` - The first number\n\
+             * `b`: The second\n  number\n\
+             \n\
+             # Examples\n\
+             add(1, 2)",
+        ));
+        assert_eq!(docs.summary, "Adds two numbers.");
+        assert_eq!(
+            docs.description,
+            "Wraps on overflow.\n\n# Examples\nadd(1, 2)"
+        );
+        assert_eq!(docs.argument("a"), Some("The first number"));
+        assert_eq!(docs.argument("b"), Some("The second number"));
+        assert_eq!(docs.argument("c"), None);
+    }
+
+    #[test]
+    fn test_blank_doc_lines() {
+        let f: syn::ItemFn = syn::parse_str("/// Summary\n///\n/// More\nfn f() {}").unwrap();
+        let docs = parse_docs(&doc_lines(&f.attrs));
+        assert_eq!(docs.summary, "Summary");
+        assert_eq!(docs.description, "More");
+    }
+
+    #[test]
+    fn test_summary_only() {
+        let docs = parse_docs(&lines("Spans\ntwo lines"));
+        assert_eq!(docs.summary, "Spans two lines");
+        assert_eq!(docs.description, "");
+        assert!(docs.arguments.is_empty());
+    }
+}
@@ -1,29 +1,82 @@
-use crate::{function_to_function_definition, FunctionDefinition};
-use anyhow::Result;
+use crate::{attribute_path, is_attribute_named, FunctionDefinition, ParseReport};
+use anyhow::{Context, Result};
 
 pub fn parse_file(path: &str) -> Result<impl IntoIterator<Item = FunctionDefinition>> {
-    let file = std::fs::read_to_string(path)?;
-    parse_file_str(&file)
+    Ok(parse_file_report(path)?.functions)
 }
 
 pub fn parse_file_str(content: &str) -> Result<impl IntoIterator<Item = FunctionDefinition>> {
+    Ok(parse_file_str_report(content)?.functions)
+}
+
+/// Like `parse_file`, also listing the functions that were skipped and why.
+pub fn parse_file_report(path: &str) -> Result<ParseReport> {
+    parse_file_filtered(path, &FunctionFilter::default())
+}
+
+/// Like `parse_file_report`, only looking at the functions `filter` keeps.
+/// Functions left out by the filter aren't listed as skipped.
+pub fn parse_file_filtered(path: &str, filter: &FunctionFilter) -> Result<ParseReport> {
+    let file = std::fs::read_to_string(path).with_context(|| format!("Reading {}", path))?;
+    parse_source(&file, Some(path), filter).with_context(|| format!("Parsing {}", path))
+}
+
+/// Like `parse_file_str`, also listing the functions that were skipped and
+/// why.
+pub fn parse_file_str_report(content: &str) -> Result<ParseReport> {
+    parse_source(content, None, &FunctionFilter::default())
+}
+
+/// Which of a file's functions to describe.  The default keeps them all.
+#[derive(Debug, Default, Clone)]
+pub struct FunctionFilter {
+    pub pub_only: bool,
+    /// Only functions with this attribute, matched on its last path segment
+    pub attribute: Option<String>,
+}
+
+impl FunctionFilter {
+    pub fn matches(&self, f: &syn::ItemFn) -> bool {
+        if self.pub_only && !matches!(f.vis, syn::Visibility::Public(_)) {
+            return false;
+        }
+        match &self.attribute {
+            Some(name) => f
+                .attrs
+                .iter()
+                .any(|a| is_attribute_named(&attribute_path(a), name)),
+            None => true,
+        }
+    }
+
+    /// The same check on a function that's already been described.
+    pub fn matches_definition(&self, f: &FunctionDefinition) -> bool {
+        (!self.pub_only || f.is_pub)
+            && self
+                .attribute
+                .as_ref()
+                .is_none_or(|name| f.attribute(name).is_some())
+    }
+}
+
+fn parse_source(content: &str, path: Option<&str>, filter: &FunctionFilter) -> Result<ParseReport> {
     let file_content = syn::parse_file(content)?;
 
-    let functions : Vec<_> = file_content
-        .items
-        .iter()
-        .filter_map(|item| match item {
-            syn::Item::Fn(f) => Some(f),
-            _ => None,
-        })
-        .filter_map(|f| function_to_function_definition(f).ok())
-        .collect();
-    Ok(functions)
+    let mut report = ParseReport::default();
+    for item in file_content.items.iter() {
+        if let syn::Item::Fn(f) = item {
+            if filter.matches(f) {
+                report.add(f, path)?;
+            }
+        }
+    }
+    Ok(report)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::SkipReason;
 
     #[test]
     fn test_happy_path() {
@@ -34,4 +87,59 @@ mod tests {
         let functions = parse_file_str(file_content).unwrap();
         assert_eq!(functions.into_iter().count(), 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_skipped() {
+        let file_content = r#"
+            fn foo() {}
+            fn bar((a, b): (i32, i32)) {}
+        "#;
+        let report = parse_file_str_report(file_content).unwrap();
+        assert_eq!(report.functions.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].name, "bar");
+        assert_eq!(report.skipped[0].span.line, 3);
+        assert_eq!(report.skipped[0].span.column, 19);
+        assert_eq!(
+            report.skipped[0].reason,
+            SkipReason::Pattern("(a , b)".to_string())
+        );
+        assert!(report.ensure_complete().is_err());
+
+        // The plain parse leaves it out
+        let functions = parse_file_str(file_content).unwrap();
+        assert_eq!(functions.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_filter() {
+        let file = syn::parse_file(
+            r#"
+            #[node] pub fn a() {}
+            #[ive::node] fn b() {}
+            pub fn c() {}
+        "#,
+        )
+        .unwrap();
+        let names = |filter: FunctionFilter| {
+            file.items
+                .iter()
+                .filter_map(|item| match item {
+                    syn::Item::Fn(f) if filter.matches(f) => Some(f.sig.ident.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(FunctionFilter::default()), vec!["a", "b", "c"]);
+        let pub_only = FunctionFilter {
+            pub_only: true,
+            ..Default::default()
+        };
+        assert_eq!(names(pub_only), vec!["a", "c"]);
+        let attribute = FunctionFilter {
+            pub_only: false,
+            attribute: Some("node".to_string()),
+        };
+        assert_eq!(names(attribute), vec!["a", "b"]);
+    }
+}
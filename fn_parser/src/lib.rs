@@ -2,11 +2,39 @@ use anyhow::Result;
 use quote::ToTokens;
 use serde::{Serialize, Deserialize};
 
+pub mod crate_scanning;
+pub mod diagnostics;
+pub mod diff;
+pub mod docs;
 pub mod file_parsing;
+pub mod library;
+pub mod output;
+pub mod types;
+
+pub use diagnostics::{ParseReport, SkipReason, SkippedItem};
+pub use docs::Documentation;
+pub use types::TypeRef;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TypeDefinition {
     pub tokens: Vec<String>,
+    /// The same type with its structure, `None` in JSON from before it was
+    /// recorded
+    #[serde(default)]
+    pub type_ref: Option<TypeRef>,
+}
+
+impl TypeDefinition {
+    /// The structured type, or the tokens as `TypeRef::Other` when it
+    /// wasn't recorded.
+    pub fn to_type_ref(&self) -> TypeRef {
+        match &self.type_ref {
+            Some(type_ref) => type_ref.clone(),
+            None => TypeRef::Other {
+                tokens: self.tokens.join(" "),
+            },
+        }
+    }
 }
 
 
@@ -14,6 +42,31 @@ pub struct TypeDefinition {
 pub struct InputDefinition {
     pub name: String,
     pub ty: TypeDefinition,
+    /// From the function's `# Arguments` docs
+    #[serde(default)]
+    pub doc: String,
+    /// The expression in a `#[default = expr]` attribute on the argument
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// An attribute on the function other than its docs, like
+/// `#[make_dynamicable]` or `#[node(...)]`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttributeDefinition {
+    /// The attribute's path, e.g. `ive_macros::node`
+    pub path: String,
+    /// The tokens in the brackets or after the `=`, empty if there are none
+    pub args: String,
+}
+
+/// Where a function is in the source.  Lines start at 1 and columns at 0.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// Only known when parsing a file
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +75,68 @@ pub struct FunctionDefinition {
     pub name: String,
     pub inputs: Vec<InputDefinition>,
     pub output: TypeDefinition,
+    #[serde(default)]
+    pub docs: Documentation,
+    #[serde(default)]
+    pub attributes: Vec<AttributeDefinition>,
+    /// The generic parameters, e.g. `T : Clone`
+    #[serde(default)]
+    pub generics: Vec<String>,
+    /// The where clause predicates
+    #[serde(default)]
+    pub where_clause: Vec<String>,
+    #[serde(default)]
+    pub span: SourceSpan,
+    /// The module the function is in, e.g. `crate::math`.  Only known when
+    /// scanning a crate.
+    #[serde(default)]
+    pub module_path: String,
+}
+
+impl FunctionDefinition {
+    /// The function's full path, e.g. `crate::math::add`, or just its name
+    /// when the module isn't known.
+    pub fn path(&self) -> String {
+        match self.module_path.is_empty() {
+            true => self.name.clone(),
+            false => format!("{}::{}", self.module_path, self.name),
+        }
+    }
+
+    /// The signature as it would be written, e.g.
+    /// `fn add(a: i32, b: i32) -> i32`, without the where clause.
+    pub fn signature(&self) -> String {
+        let generics = match self.generics.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", self.generics.join(", ")),
+        };
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|i| format!("{}: {}", i.name, i.ty.to_type_ref()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let output = self.output.to_type_ref();
+        let output = match output.is_unit() {
+            true => String::new(),
+            false => format!(" -> {}", output),
+        };
+        format!("fn {}{}({}){}", self.name, generics, inputs, output)
+    }
+
+    /// The attribute with the given last path segment, e.g. `node` for
+    /// both `#[node]` and `#[ive_macros::node]`.
+    pub fn attribute(&self, name: &str) -> Option<&AttributeDefinition> {
+        self.attributes
+            .iter()
+            .find(|a| is_attribute_named(&a.path, name))
+    }
+}
+
+/// Whether an attribute path like `ive_macros::node` names the attribute
+/// `name`, matched on its last segment.
+pub(crate) fn is_attribute_named(path: &str, name: &str) -> bool {
+    path.rsplit("::").next() == Some(name)
 }
 
 fn type_to_type_definition(ty: &syn::Type) -> Result<TypeDefinition> {
@@ -32,38 +147,107 @@ fn type_to_type_definition(ty: &syn::Type) -> Result<TypeDefinition> {
         .collect::<Vec<String>>();
     Ok(TypeDefinition {
         tokens: tokens_as_string,
+        type_ref: Some(TypeRef::from_syn(ty)),
     })
 }
 
 fn return_to_type_definition(ret: &syn::ReturnType) -> Result<TypeDefinition> {
     match ret {
         syn::ReturnType::Type(_, ty) => type_to_type_definition(ty),
-        _ => Ok(TypeDefinition { tokens: vec![] }),
+        _ => Ok(TypeDefinition {
+            tokens: vec![],
+            type_ref: Some(TypeRef::unit()),
+        }),
     }
 }
 
+pub(crate) fn attribute_path(attr: &syn::Attribute) -> String {
+    attr.path()
+        .segments
+        .iter()
+        .map(|s| s.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn attribute_to_attribute_definition(attr: &syn::Attribute) -> AttributeDefinition {
+    let path = attribute_path(attr);
+    let args = match &attr.meta {
+        syn::Meta::Path(_) => String::new(),
+        syn::Meta::List(list) => list.tokens.to_string(),
+        syn::Meta::NameValue(nv) => nv.value.to_token_stream().to_string(),
+    };
+    AttributeDefinition { path, args }
+}
+
+/// Describes a function.  Fails with a `SkippedItem` for functions that
+/// can't be described, see `diagnostics::check_function`.
 pub fn function_to_function_definition(f: &syn::ItemFn) -> Result<FunctionDefinition> {
-    
+    diagnostics::check_function(f)?;
+    let docs = docs::parse_docs(&docs::doc_lines(&f.attrs));
 
     let inputs = f
         .sig
         .inputs
         .iter()
         .map(|input| match input {
-            syn::FnArg::Receiver(_) => panic!("Receiver not supported"),
+            syn::FnArg::Receiver(_) => Err(anyhow::anyhow!("Receiver not supported")),
             syn::FnArg::Typed(typed) => {
-                let name = typed.pat.to_token_stream().to_string();
+                let name = match &*typed.pat {
+                    syn::Pat::Ident(pat) => pat.ident.to_string(),
+                    pat => pat.to_token_stream().to_string(),
+                };
                 let ty = type_to_type_definition(&typed.ty)?;
-                Ok(InputDefinition { name, ty })
+                let doc = docs.argument(&name).unwrap_or_default().to_string();
+                let default = typed
+                    .attrs
+                    .iter()
+                    .find(|a| a.path().is_ident("default"))
+                    .and_then(|a| match &a.meta {
+                        syn::Meta::NameValue(nv) => Some(nv.value.to_token_stream().to_string()),
+                        _ => None,
+                    });
+                Ok(InputDefinition {
+                    name,
+                    ty,
+                    doc,
+                    default,
+                })
             }
         })
         .collect::<Result<Vec<InputDefinition>>>()?;
 
     let output = return_to_type_definition(&f.sig.output)?;
 
-    let is_pub = match f.vis {
-        syn::Visibility::Public(_) => true,
-        _ => false,
+    let is_pub = matches!(f.vis, syn::Visibility::Public(_));
+
+    let attributes = f
+        .attrs
+        .iter()
+        .filter(|a| !a.path().is_ident("doc"))
+        .map(attribute_to_attribute_definition)
+        .collect();
+    let generics = f
+        .sig
+        .generics
+        .params
+        .iter()
+        .map(|p| p.to_token_stream().to_string())
+        .collect();
+    let where_clause = f
+        .sig
+        .generics
+        .where_clause
+        .iter()
+        .flat_map(|w| w.predicates.iter())
+        .map(|p| p.to_token_stream().to_string())
+        .collect();
+
+    let start = f.sig.ident.span().start();
+    let span = SourceSpan {
+        file: None,
+        line: start.line,
+        column: start.column,
     };
 
     Ok(FunctionDefinition {
@@ -71,6 +255,12 @@ pub fn function_to_function_definition(f: &syn::ItemFn) -> Result<FunctionDefini
         name: f.sig.ident.to_string(),
         inputs,
         output,
+        docs,
+        attributes,
+        generics,
+        where_clause,
+        span,
+        module_path: String::new(),
     })
 }
 
@@ -117,6 +307,69 @@ mod tests {
         assert_eq!(function_definition.output.tokens, vec!["u128"]);
     }
 
+    #[test]
+    fn test_docs_and_attributes() {
+        let test_fn = r#"
+            /// Adds two numbers.
+            ///
+            /// # Arguments
+            ///
+            /// * `a` - The first number
+            /// * `b` - The second number
+            #[make_dynamicable()]
+            #[ive_macros::node(name = "Add")]
+            pub fn add(a: i32, b: i32) -> i32 {
+                a + b
+            }
+        "#;
+        let itemfn = syn::parse_str::<syn::ItemFn>(test_fn).unwrap();
+        let function_definition = function_to_function_definition(&itemfn).unwrap();
+        assert_eq!(function_definition.docs.summary, "Adds two numbers.");
+        assert_eq!(function_definition.inputs[0].doc, "The first number");
+        assert_eq!(function_definition.inputs[1].doc, "The second number");
+
+        assert_eq!(function_definition.attributes.len(), 2);
+        let dynamicable = function_definition.attribute("make_dynamicable").unwrap();
+        assert_eq!(dynamicable.path, "make_dynamicable");
+        assert_eq!(dynamicable.args, "");
+        let node = function_definition.attribute("node").unwrap();
+        assert_eq!(node.path, "ive_macros::node");
+        assert_eq!(node.args, "name = \"Add\"");
+    }
+
+    #[test]
+    fn test_generics_and_span() {
+        let test_fn = "\n\n    fn first<T: Clone, U>(a: &[T], b: U) -> T\n    where\n        U: Copy,\n    {\n        a[0].clone()\n    }";
+        let itemfn = syn::parse_str::<syn::ItemFn>(test_fn).unwrap();
+        let function_definition = function_to_function_definition(&itemfn).unwrap();
+        assert_eq!(function_definition.generics, vec!["T : Clone", "U"]);
+        assert_eq!(function_definition.where_clause, vec!["U : Copy"]);
+        assert_eq!(
+            function_definition.signature(),
+            "fn first<T : Clone, U>(a: &[T], b: U) -> T"
+        );
+        assert_eq!(
+            function_definition.span,
+            SourceSpan {
+                file: None,
+                line: 3,
+                column: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_receiver_errors() {
+        let itemfn = syn::parse_str::<syn::ItemFn>("fn test_fn(&self, mut a: u32) {}").unwrap();
+        let err = function_to_function_definition(&itemfn).unwrap_err();
+        let skipped = err.downcast_ref::<SkippedItem>().unwrap();
+        assert_eq!(skipped.reason, SkipReason::Receiver);
+
+        let itemfn = syn::parse_str::<syn::ItemFn>("fn test_fn(mut a: u32) {}").unwrap();
+        let function_definition = function_to_function_definition(&itemfn).unwrap();
+        assert_eq!(function_definition.inputs[0].name, "a");
+    }
+
     #[test]
     fn test_reference_outputs() {
         let test_fn = r#"
@@ -134,4 +387,12 @@ mod tests {
         assert_eq!(function_definition.inputs[1].ty.tokens, vec!["u64"]);
         assert_eq!(function_definition.output.tokens, vec!["&", "u128"]);
     }
+
+    #[test]
+    fn test_old_json() {
+        let json = r#"{"is_pub": true, "name": "f", "inputs": [], "output": {"tokens": ["u32"]}}"#;
+        let function_definition = serde_json::from_str::<FunctionDefinition>(json).unwrap();
+        assert_eq!(function_definition.output.tokens, vec!["u32"]);
+        assert!(function_definition.output.type_ref.is_none());
+    }
 }
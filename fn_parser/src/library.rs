@@ -0,0 +1,288 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use prototype_network::description::{Library, NodeDescriptor, PortDescriptor};
+
+use crate::crate_scanning::scan_crate_report;
+use crate::{FunctionDefinition, InputDefinition, TypeDefinition, TypeRef};
+
+/// Name given to a function's output port.  The same as
+/// `ive::dyn_call::OUTPUT_PORT`, which saved graphs connect to.
+pub const OUTPUT_PORT: &str = "value";
+
+/// Describes a function as a node the way `make_dynamicable` runs it.  The
+/// docs summary and description become the node's description, and each
+/// argument's `# Arguments` entry its port's.  Inputs carry the type the
+/// node reads, and anything but `()` is returned on one output, tuples
+/// included.
+pub fn function_to_node(f: &FunctionDefinition) -> NodeDescriptor {
+    let description = match f.docs.description.is_empty() {
+        true => f.docs.summary.clone(),
+        false => format!("{}\n\n{}", f.docs.summary, f.docs.description),
+    };
+    let inputs = f
+        .inputs
+        .iter()
+        .map(|i| {
+            PortDescriptor::new(&i.name, &i.doc, &port_type(&i.ty)).with_optional(is_optional(i))
+        })
+        .collect();
+
+    let output = f.output.to_type_ref();
+    let outputs = match output.is_unit() {
+        true => vec![],
+        false => vec![PortDescriptor::new(
+            OUTPUT_PORT,
+            "Output",
+            &output.dereferenced().to_string(),
+        )],
+    };
+
+    NodeDescriptor::new(&f.name, &description, inputs, outputs)
+}
+
+/// A library of the public functions.
+pub fn functions_to_library<'a>(
+    name: &str,
+    crate_name: &str,
+    description: &str,
+    functions: impl IntoIterator<Item = &'a FunctionDefinition>,
+) -> Library {
+    let mut library = Library::new(name, crate_name, description);
+    for f in functions.into_iter().filter(|f| f.is_pub) {
+        library.add_node(function_to_node(f));
+    }
+    library
+}
+
+/// Builds the library for a crate from its source.  `path` is the crate's
+/// directory or its root source file.  The name and description come from
+/// `Cargo.toml` when there is one.
+///
+/// Fails if any public function couldn't be parsed, so nothing silently
+/// goes missing from the library.
+pub fn crate_library(path: impl AsRef<Path>, attribute: Option<&str>) -> Result<Library> {
+    let (root, manifest) = crate_paths(path.as_ref())?;
+    let report = scan_crate_report(&root, attribute)?;
+
+    let skipped = report
+        .skipped
+        .iter()
+        .filter(|s| s.is_pub)
+        .collect::<Vec<_>>();
+    if !skipped.is_empty() {
+        let lines = skipped.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        return Err(anyhow!(
+            "{} public function(s) couldn't be parsed:\n{}",
+            skipped.len(),
+            lines.join("\n")
+        ));
+    }
+
+    let (name, description) = match manifest {
+        Some(manifest) => (
+            package_field(&manifest, "name")?,
+            package_field(&manifest, "description")?,
+        ),
+        None => (None, None),
+    };
+    let crate_name = name
+        .or_else(|| {
+            let dir = root.parent()?.parent()?;
+            Some(dir.file_name()?.to_str()?.to_string())
+        })
+        .unwrap_or_default();
+    let description = description.unwrap_or_default();
+
+    Ok(functions_to_library(
+        &crate_name,
+        &crate_name,
+        &description,
+        &report.functions,
+    ))
+}
+
+/// The root source file and `Cargo.toml`, if any, for a crate path.
+fn crate_paths(path: &Path) -> Result<(PathBuf, Option<PathBuf>)> {
+    if path.is_file() {
+        let manifest = path
+            .parent()
+            .and_then(Path::parent)
+            .map(|dir| dir.join("Cargo.toml"))
+            .filter(|m| m.is_file());
+        return Ok((path.to_path_buf(), manifest));
+    }
+    let root = ["src/lib.rs", "src/main.rs"]
+        .iter()
+        .map(|f| path.join(f))
+        .find(|f| f.is_file())
+        .ok_or_else(|| anyhow!("No src/lib.rs or src/main.rs in {}", path.display()))?;
+    let manifest = Some(path.join("Cargo.toml")).filter(|m| m.is_file());
+    Ok((root, manifest))
+}
+
+/// Reads a string field from the `[package]` table of a manifest.  A field
+/// set with `field.workspace = true` comes from `[workspace.package]` in
+/// the nearest manifest up the tree with a `[workspace]`.
+fn package_field(manifest: &Path, field: &str) -> Result<Option<String>> {
+    let table = read_manifest(manifest)?;
+    let value = table.get("package").and_then(|p| p.get(field));
+    let inherited = value
+        .and_then(|v| v.get("workspace"))
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    if !inherited {
+        return Ok(value.and_then(toml::Value::as_str).map(str::to_string));
+    }
+
+    for dir in manifest.ancestors().skip(1) {
+        let root = dir.join("Cargo.toml");
+        if !root.is_file() {
+            continue;
+        }
+        let table = read_manifest(&root)?;
+        if let Some(workspace) = table.get("workspace") {
+            let value = workspace.get("package").and_then(|p| p.get(field));
+            return Ok(value.and_then(toml::Value::as_str).map(str::to_string));
+        }
+    }
+    Err(anyhow!(
+        "{} inherits {} but isn't in a workspace",
+        manifest.display(),
+        field
+    ))
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Table> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Parsing {}", path.display()))
+}
+
+/// Whether the node runs with nothing connected to the input: it's an
+/// `Option`, has a `#[default]` or is a slice taking any number of
+/// connections.
+fn is_optional(input: &InputDefinition) -> bool {
+    let ty = input.ty.to_type_ref();
+    input.default.is_some()
+        || ty.option_inner().is_some()
+        || matches!(ty.dereferenced(), TypeRef::Slice { .. })
+}
+
+/// The type an input port carries, see `TypeRef::input_value`.
+fn port_type(ty: &TypeDefinition) -> String {
+    ty.to_type_ref().input_value().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_parsing::parse_file_str;
+
+    #[test]
+    fn test_function_to_node() {
+        let source = r#"
+            /// Splits a number.
+            ///
+            /// Rounds down.
+            ///
+            /// # Arguments
+            ///
+            /// * `value` - What to split
+            pub fn split(value: &u32) -> (u32, u32) {
+                (value / 2, value - value / 2)
+            }
+            pub fn nothing() {}
+            fn private() -> u32 { 0 }
+        "#;
+        let functions = parse_file_str(source)
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let library = functions_to_library("lib", "lib_crate", "A library", &functions);
+        assert_eq!(library.nodes().len(), 2);
+
+        let split = &library.nodes()[0];
+        assert_eq!(split.name(), "split");
+        assert_eq!(split.description(), "Splits a number.\n\nRounds down.");
+        assert_eq!(
+            split.inputs(),
+            &[PortDescriptor::new("value", "What to split", "u32")]
+        );
+        assert_eq!(
+            split.outputs(),
+            &[PortDescriptor::new("value", "Output", "(u32, u32)")]
+        );
+
+        assert!(library.nodes()[1].outputs().is_empty());
+    }
+
+    #[test]
+    fn test_input_types() {
+        let source =
+            "pub fn total(values: &[&i32], scale: Option<i32>, name: &String) -> i32 { 0 }";
+        let function = parse_file_str(source).unwrap().into_iter().next().unwrap();
+        let node = function_to_node(&function);
+        let types = node.inputs().iter().map(|p| p.ty()).collect::<Vec<_>>();
+        assert_eq!(types, vec!["i32", "i32", "String"]);
+        let optional = node
+            .inputs()
+            .iter()
+            .map(|p| p.is_optional())
+            .collect::<Vec<_>>();
+        assert_eq!(optional, vec![true, true, false]);
+
+        let source = "pub fn increment(a: i32, #[default = 1] by: i32) -> i32 { a + by }";
+        let function = parse_file_str(source).unwrap().into_iter().next().unwrap();
+        assert_eq!(function.inputs[1].default.as_deref(), Some("1"));
+        assert!(function_to_node(&function).inputs()[1].is_optional());
+    }
+
+    #[test]
+    fn test_sample_nodes() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../sample_nodes");
+        let library = crate_library(path, None).unwrap();
+        assert_eq!(library.name(), "sample_nodes");
+        assert_eq!(library.crate_name(), "sample_nodes");
+
+        let names = library.nodes().iter().map(|n| n.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["add", "three", "four", "copy_u32"]);
+
+        let add = &library.nodes()[0];
+        assert_eq!(add.description(), "Add two u32 numbers together");
+        assert_eq!(
+            add.inputs(),
+            &[
+                PortDescriptor::new("left", "Left input", "u32"),
+                PortDescriptor::new("right", "Right input", "u32"),
+            ]
+        );
+        assert_eq!(
+            add.outputs(),
+            &[PortDescriptor::new(OUTPUT_PORT, "Output", "u32")]
+        );
+    }
+
+    #[test]
+    fn test_package_field() {
+        let dir = std::env::temp_dir().join(format!("fn_parser_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nodes")).unwrap();
+        let workspace = "[workspace]\nmembers = ['nodes']\n\n\
+                         [workspace.package]\ndescription = \"Shared\"\n";
+        std::fs::write(dir.join("Cargo.toml"), workspace).unwrap();
+        let manifest = dir.join("nodes/Cargo.toml");
+        let crate_manifest = "[package]\nname = 'nodes' # the crate\n\
+                              description.workspace = true\n\n\
+                              [dependencies]\nversion = \"no\"\n";
+        std::fs::write(&manifest, crate_manifest).unwrap();
+
+        let name = package_field(&manifest, "name");
+        let description = package_field(&manifest, "description");
+        let version = package_field(&manifest, "version");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(name.unwrap(), Some("nodes".to_string()));
+        assert_eq!(description.unwrap(), Some("Shared".to_string()));
+        assert_eq!(version.unwrap(), None);
+    }
+}
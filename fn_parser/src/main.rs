@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use fn_parser::diff::diff_files;
+use fn_parser::file_parsing::{parse_file_filtered, FunctionFilter};
+use fn_parser::library::{crate_library, functions_to_library};
+use fn_parser::output::{
+    self, diff_table, functions_table, library_diff_table, library_table, FileFunctions,
+};
+use prototype_network::compat::diff_libraries;
+use prototype_network::description::Library;
+use prototype_network::render::library_to_markdown;
+
+/// Everything worked
+const EXIT_OK: u8 = 0;
+/// Some functions couldn't be parsed, or `diff` found differences (breaking
+/// ones with `--nodes`)
+const EXIT_FOUND: u8 = 1;
+/// A file couldn't be read or parsed at all
+const EXIT_ERROR: u8 = 2;
+
+/// Describes the functions in Rust source, for building node libraries.
+///
+/// Exits with 1 when functions had to be skipped (or `diff` finds
+/// differences) and 2 on errors.
+#[derive(Parser)]
+#[command(name = "fn_parser", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+    /// Write to this file instead of stdout
+    #[arg(long, short, global = true)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the functions in files, directories or glob patterns
+    Functions {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Generate a node library from a crate's public functions
+    Library {
+        /// The crate's directory or root source file
+        path: PathBuf,
+        /// Only functions with this attribute
+        #[arg(long)]
+        attribute: Option<String>,
+        /// The library name, defaults to the crate name
+        #[arg(long)]
+        name: Option<String>,
+        /// Defaults to the crate description
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Compare the function signatures in two sets of files.  Either side
+    /// can also be JSON written by `functions --format json`.
+    Diff {
+        old: String,
+        new: String,
+        /// Compare the public functions as library nodes, flagging changes
+        /// that break saved graphs.  Either side can also be JSON written by
+        /// `library --format json`.
+        #[arg(long)]
+        nodes: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(clap::Args)]
+struct FilterArgs {
+    /// Only public functions
+    #[arg(long)]
+    pub_only: bool,
+    /// Only functions with this attribute, e.g. `make_dynamicable`
+    #[arg(long)]
+    attribute: Option<String>,
+}
+
+impl From<FilterArgs> for FunctionFilter {
+    fn from(args: FilterArgs) -> Self {
+        FunctionFilter {
+            pub_only: args.pub_only,
+            attribute: args.attribute,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Pretty,
+    Ron,
+    Table,
+    /// Only for `library`
+    Markdown,
+}
+
+impl Format {
+    fn output_format(self) -> Result<output::Format> {
+        Ok(match self {
+            Format::Json => output::Format::Json,
+            Format::Pretty => output::Format::PrettyJson,
+            Format::Ron => output::Format::Ron,
+            Format::Table => output::Format::Table,
+            Format::Markdown => bail!("Markdown output is only for libraries"),
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<u8> {
+    let (rendered, code) = match cli.command {
+        Command::Functions { paths, filter } => {
+            let files = parse_paths(&paths, &filter.into())?;
+            let code = report_skipped(&files);
+            let format = cli.format.output_format()?;
+            (
+                output::render(files.as_slice(), format, functions_table)?,
+                code,
+            )
+        }
+        Command::Library {
+            path,
+            attribute,
+            name,
+            description,
+        } => {
+            let generated = crate_library(&path, attribute.as_deref())?;
+            let mut library = Library::new(
+                name.as_deref().unwrap_or(generated.name()),
+                generated.crate_name(),
+                description.as_deref().unwrap_or(generated.description()),
+            );
+            for node in generated.nodes() {
+                library.add_node(node.clone());
+            }
+            let rendered = match cli.format {
+                Format::Markdown => library_to_markdown(&library),
+                format => output::render(&library, format.output_format()?, library_table)?,
+            };
+            (rendered, EXIT_OK)
+        }
+        Command::Diff {
+            old,
+            new,
+            nodes: true,
+            filter,
+        } => {
+            let filter = filter.into();
+            let old = load_library(&old, &filter)?;
+            let new = load_library(&new, &filter)?;
+            let diff = diff_libraries(&old, &new);
+            for change in diff.breaking() {
+                eprintln!("breaking: {}", change);
+            }
+            let code = if diff.is_breaking() {
+                EXIT_FOUND
+            } else {
+                EXIT_OK
+            };
+            let format = cli.format.output_format()?;
+            (output::render(&diff, format, library_diff_table)?, code)
+        }
+        Command::Diff {
+            old, new, filter, ..
+        } => {
+            let filter = filter.into();
+            let old = load_files(&old, &filter)?;
+            let new = load_files(&new, &filter)?;
+            let diff = diff_files(&old, &new);
+            let code = if diff.is_empty() { EXIT_OK } else { EXIT_FOUND };
+            let format = cli.format.output_format()?;
+            (output::render(&diff, format, diff_table)?, code)
+        }
+    };
+
+    match cli.output {
+        Some(path) => std::fs::write(&path, rendered)
+            .with_context(|| format!("Writing {}", path.display()))?,
+        None => print!("{}", rendered),
+    }
+    Ok(code)
+}
+
+/// Parses every Rust file the paths name.  Directories are searched
+/// recursively, leaving out `target`, and paths with `*`, `?` or `[` are
+/// glob patterns.
+fn parse_paths(paths: &[String], filter: &FunctionFilter) -> Result<Vec<FileFunctions>> {
+    let mut files = vec![];
+    for path in paths {
+        files.extend(expand_path(path)?);
+    }
+    files.sort();
+    files.dedup();
+
+    files
+        .iter()
+        .map(|file| {
+            let path = file.display().to_string();
+            let report = parse_file_filtered(&path, filter)?;
+            Ok(FileFunctions {
+                path,
+                functions: report.functions,
+                skipped: report.skipped,
+            })
+        })
+        .collect()
+}
+
+fn expand_path(path: &str) -> Result<Vec<PathBuf>> {
+    let pattern = if path.contains(['*', '?', '[']) {
+        path.to_string()
+    } else if Path::new(path).is_dir() {
+        format!("{}/**/*.rs", path.trim_end_matches('/'))
+    } else {
+        return Ok(vec![PathBuf::from(path)]);
+    };
+
+    let mut files = vec![];
+    for entry in glob::glob(&pattern).with_context(|| format!("Bad pattern {}", path))? {
+        let file = entry?;
+        if file.components().any(|c| c.as_os_str() == "target") || !file.is_file() {
+            continue;
+        }
+        files.push(file);
+    }
+    if files.is_empty() {
+        return Err(anyhow!("No Rust files match {}", path));
+    }
+    Ok(files)
+}
+
+/// Lists skipped functions on stderr, returning the exit code for them.
+fn report_skipped(files: &[FileFunctions]) -> u8 {
+    let mut code = EXIT_OK;
+    for skipped in files.iter().flat_map(|f| f.skipped.iter()) {
+        eprintln!("{}", skipped);
+        code = EXIT_FOUND;
+    }
+    code
+}
+
+/// The files for one side of a diff: JSON from `functions`, or source paths
+/// to parse.
+fn load_files(path: &str, filter: &FunctionFilter) -> Result<Vec<FileFunctions>> {
+    let mut files = match path.ends_with(".json") {
+        true => {
+            let json =
+                std::fs::read_to_string(path).with_context(|| format!("Reading {}", path))?;
+            serde_json::from_str::<Vec<FileFunctions>>(&json)
+                .with_context(|| format!("Parsing {}", path))?
+        }
+        false => parse_paths(&[path.to_string()], filter)?,
+    };
+    for file in files.iter_mut() {
+        file.functions.retain(|f| filter.matches_definition(f));
+    }
+    Ok(files)
+}
+
+/// One side of a node diff: library JSON, or functions to make one from.
+fn load_library(path: &str, filter: &FunctionFilter) -> Result<Library> {
+    if path.ends_with(".json") {
+        let json = std::fs::read_to_string(path).with_context(|| format!("Reading {}", path))?;
+        if let Ok(library) = serde_json::from_str::<Library>(&json) {
+            return Ok(library);
+        }
+    }
+    let files = load_files(path, filter)?;
+    let functions = files.iter().flat_map(|f| f.functions.iter());
+    Ok(functions_to_library(path, path, "", functions))
+}
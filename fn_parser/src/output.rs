@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use prototype_network::compat::LibraryDiff;
+use prototype_network::description::{Library, PortDescriptor};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::FunctionDiff;
+use crate::{FunctionDefinition, SkippedItem};
+
+/// The functions parsed from one file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileFunctions {
+    pub path: String,
+    pub functions: Vec<FunctionDefinition>,
+    #[serde(default)]
+    pub skipped: Vec<SkippedItem>,
+}
+
+/// How to print results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Compact JSON on one line
+    Json,
+    PrettyJson,
+    Ron,
+    /// Aligned columns for reading in a terminal
+    Table,
+}
+
+/// Renders anything serializable in the data formats.  `table` is used for
+/// `Format::Table`.
+pub fn render<T: Serialize + ?Sized>(
+    value: &T,
+    format: Format,
+    table: impl Fn(&T) -> String,
+) -> Result<String> {
+    Ok(match format {
+        Format::Json => serde_json::to_string(value)? + "\n",
+        Format::PrettyJson => serde_json::to_string_pretty(value)? + "\n",
+        Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())? + "\n",
+        Format::Table => table(value),
+    })
+}
+
+/// One row per function, with where it is and its signature.
+pub fn functions_table(files: &[FileFunctions]) -> String {
+    let mut rows = vec![];
+    for file in files {
+        for f in file.functions.iter() {
+            let visibility = if f.is_pub { "pub" } else { "" };
+            rows.push(vec![
+                f.path(),
+                visibility.to_string(),
+                f.signature(),
+                format!("{}:{}", file.path, f.span.line),
+            ]);
+        }
+        for s in file.skipped.iter() {
+            rows.push(vec![
+                s.name.clone(),
+                if s.is_pub { "pub" } else { "" }.to_string(),
+                format!("skipped: {}", s.reason),
+                format!("{}:{}", file.path, s.span.line),
+            ]);
+        }
+    }
+    table(&["FUNCTION", "VIS", "SIGNATURE", "LOCATION"], &rows)
+}
+
+/// One row per node, listing its ports as `name: type`.
+pub fn library_table(library: &Library) -> String {
+    let ports = |ports: &[PortDescriptor]| {
+        ports
+            .iter()
+            .map(|p| format!("{}: {}", p.name(), p.ty()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let rows = library
+        .nodes()
+        .iter()
+        .map(|n| vec![n.name().to_string(), ports(n.inputs()), ports(n.outputs())])
+        .collect::<Vec<_>>();
+    table(&["NODE", "INPUTS", "OUTPUTS"], &rows)
+}
+
+/// One row per difference.
+pub fn diff_table(diff: &FunctionDiff) -> String {
+    let mut rows = vec![];
+    for path in diff.added.iter() {
+        rows.push(vec!["added".to_string(), path.clone(), String::new()]);
+    }
+    for path in diff.removed.iter() {
+        rows.push(vec!["removed".to_string(), path.clone(), String::new()]);
+    }
+    for changed in diff.changed.iter() {
+        let detail = format!("{} => {}", changed.old, changed.new);
+        rows.push(vec!["changed".to_string(), changed.path.clone(), detail]);
+    }
+    table(&["CHANGE", "FUNCTION", "DETAIL"], &rows)
+}
+
+/// One row per change, marking the ones that break saved graphs.
+pub fn library_diff_table(diff: &LibraryDiff) -> String {
+    let rows = diff
+        .changes
+        .iter()
+        .map(|c| {
+            let breaking = if c.is_breaking() { "yes" } else { "" };
+            vec![breaking.to_string(), c.node().to_string(), c.to_string()]
+        })
+        .collect::<Vec<_>>();
+    table(&["BREAKING", "NODE", "CHANGE"], &rows)
+}
+
+/// Lays out rows in columns padded to the widest cell, under a header.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let headers = headers.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    for row in std::iter::once(&headers).chain(rows) {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths.iter()) {
+            _ = write!(line, "{:width$}  ", cell, width = width);
+        }
+        _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_parsing::parse_file_str_report;
+
+    fn files() -> Vec<FileFunctions> {
+        let report =
+            parse_file_str_report("pub fn add(a: u32, b: u32) -> u32 { a + b }\nfn pair(&self) {}")
+                .unwrap();
+        vec![FileFunctions {
+            path: "src/lib.rs".to_string(),
+            functions: report.functions,
+            skipped: report.skipped,
+        }]
+    }
+
+    #[test]
+    fn test_functions_table() {
+        let table = render(files().as_slice(), Format::Table, functions_table).unwrap();
+        assert_eq!(
+            table,
+            "FUNCTION  VIS  SIGNATURE                      LOCATION\n\
+             add       pub  fn add(a: u32, b: u32) -> u32  src/lib.rs:1\n\
+             pair           skipped: takes self            src/lib.rs:2\n"
+        );
+    }
+
+    #[test]
+    fn test_data_formats() {
+        let files = files();
+        let json = render(files.as_slice(), Format::Json, functions_table).unwrap();
+        assert_eq!(json.lines().count(), 1);
+        let pretty = render(files.as_slice(), Format::PrettyJson, functions_table).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+        );
+
+        let ron = render(files.as_slice(), Format::Ron, functions_table).unwrap();
+        let parsed = ron::from_str::<Vec<FileFunctions>>(&ron).unwrap();
+        assert_eq!(
+            parsed[0].functions[0].signature(),
+            "fn add(a: u32, b: u32) -> u32"
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+
+/// A type broken down into its structure, so tools don't have to re-parse
+/// the tokens.  Serialized with a `kind` tag, e.g.
+/// `{"kind": "path", "path": ["Option"], "args": [...]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeRef {
+    /// A named type like `std::vec::Vec<f32>`.  `args` are the generic type
+    /// arguments on the last segment.  Paths with anything else in them,
+    /// like a lifetime or `Item = T`, are kept as `Other`.
+    Path {
+        path: Vec<String>,
+        args: Vec<TypeRef>,
+    },
+    Reference {
+        mutable: bool,
+        lifetime: Option<String>,
+        elem: Box<TypeRef>,
+    },
+    /// A tuple, or `()` when empty
+    Tuple {
+        elems: Vec<TypeRef>,
+    },
+    Slice {
+        elem: Box<TypeRef>,
+    },
+    Array {
+        elem: Box<TypeRef>,
+        len: String,
+    },
+    FnPointer {
+        inputs: Vec<TypeRef>,
+        output: Box<TypeRef>,
+    },
+    /// `impl A + B`, with the bounds as written
+    ImplTrait {
+        bounds: Vec<String>,
+    },
+    /// `dyn A + B`, with the bounds as written
+    TraitObject {
+        bounds: Vec<String>,
+    },
+    /// Anything else, as its tokens
+    Other {
+        tokens: String,
+    },
+}
+
+impl TypeRef {
+    pub fn unit() -> Self {
+        TypeRef::Tuple { elems: vec![] }
+    }
+
+    pub fn from_syn(ty: &syn::Type) -> Self {
+        match ty {
+            syn::Type::Path(p) if p.qself.is_none() && is_plain_path(&p.path) => {
+                let path = p
+                    .path
+                    .segments
+                    .iter()
+                    .map(|s| s.ident.to_string())
+                    .collect();
+                let args = match p.path.segments.last().map(|s| &s.arguments) {
+                    Some(syn::PathArguments::AngleBracketed(args)) => args
+                        .args
+                        .iter()
+                        .filter_map(|a| match a {
+                            syn::GenericArgument::Type(ty) => Some(TypeRef::from_syn(ty)),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                TypeRef::Path { path, args }
+            }
+            syn::Type::Reference(r) => TypeRef::Reference {
+                mutable: r.mutability.is_some(),
+                lifetime: r.lifetime.as_ref().map(|l| l.ident.to_string()),
+                elem: Box::new(TypeRef::from_syn(&r.elem)),
+            },
+            syn::Type::Tuple(t) => TypeRef::Tuple {
+                elems: t.elems.iter().map(TypeRef::from_syn).collect(),
+            },
+            syn::Type::Slice(s) => TypeRef::Slice {
+                elem: Box::new(TypeRef::from_syn(&s.elem)),
+            },
+            syn::Type::Array(a) => TypeRef::Array {
+                elem: Box::new(TypeRef::from_syn(&a.elem)),
+                len: a.len.to_token_stream().to_string(),
+            },
+            syn::Type::BareFn(f) => TypeRef::FnPointer {
+                inputs: f.inputs.iter().map(|a| TypeRef::from_syn(&a.ty)).collect(),
+                output: Box::new(return_type(&f.output)),
+            },
+            syn::Type::ImplTrait(i) => TypeRef::ImplTrait {
+                bounds: bounds(i.bounds.iter()),
+            },
+            syn::Type::TraitObject(t) => TypeRef::TraitObject {
+                bounds: bounds(t.bounds.iter()),
+            },
+            syn::Type::Paren(p) => TypeRef::from_syn(&p.elem),
+            syn::Type::Group(g) => TypeRef::from_syn(&g.elem),
+            ty => TypeRef::Other {
+                tokens: ty.to_token_stream().to_string(),
+            },
+        }
+    }
+
+    /// The last path segment, e.g. `Vec` for `std::vec::Vec<f32>`.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            TypeRef::Path { path, .. } => path.last().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    pub fn is_unit(&self) -> bool {
+        matches!(self, TypeRef::Tuple { elems } if elems.is_empty())
+    }
+
+    /// The type arguments when this is the generic type `name`, e.g. `[T]`
+    /// for `Option<T>` with `name` `Option`.
+    pub fn generic_args(&self, name: &str) -> Option<&[TypeRef]> {
+        match self {
+            TypeRef::Path { args, .. } if self.name() == Some(name) => Some(args),
+            _ => None,
+        }
+    }
+
+    /// The `T` in `Option<T>`
+    pub fn option_inner(&self) -> Option<&TypeRef> {
+        self.generic_args("Option").and_then(|args| args.first())
+    }
+
+    /// The referenced type, looking through any `&`.
+    pub fn dereferenced(&self) -> &TypeRef {
+        match self {
+            TypeRef::Reference { elem, .. } => elem.dereferenced(),
+            ty => ty,
+        }
+    }
+
+    /// What an input of this type reads from each connection, the way
+    /// `make_dynamicable` sees it: `T` for `Option<T>`, `&T` and the
+    /// elements of a `&[T]`.
+    pub fn input_value(&self) -> &TypeRef {
+        let ty = self.option_inner().unwrap_or(self);
+        match (ty, ty.dereferenced()) {
+            (TypeRef::Reference { .. }, TypeRef::Slice { elem }) => elem.dereferenced(),
+            (_, ty) => ty,
+        }
+    }
+
+    /// The separate values an output of this type holds: each element of a
+    /// tuple, nothing for `()`, or just the type itself.
+    pub fn elements(&self) -> Vec<&TypeRef> {
+        match self {
+            TypeRef::Tuple { elems } => elems.iter().collect(),
+            ty => vec![ty],
+        }
+    }
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let list = |types: &[TypeRef]| {
+            types
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            TypeRef::Path { path, args } => {
+                write!(f, "{}", path.join("::"))?;
+                if !args.is_empty() {
+                    write!(f, "<{}>", list(args))?;
+                }
+                Ok(())
+            }
+            TypeRef::Reference {
+                mutable,
+                lifetime,
+                elem,
+            } => {
+                write!(f, "&")?;
+                if let Some(lifetime) = lifetime {
+                    write!(f, "'{} ", lifetime)?;
+                }
+                if *mutable {
+                    write!(f, "mut ")?;
+                }
+                write!(f, "{}", elem)
+            }
+            TypeRef::Tuple { elems } if elems.len() == 1 => write!(f, "({},)", elems[0]),
+            TypeRef::Tuple { elems } => write!(f, "({})", list(elems)),
+            TypeRef::Slice { elem } => write!(f, "[{}]", elem),
+            TypeRef::Array { elem, len } => write!(f, "[{}; {}]", elem, len),
+            TypeRef::FnPointer { inputs, output } => {
+                write!(f, "fn({})", list(inputs))?;
+                if !output.is_unit() {
+                    write!(f, " -> {}", output)?;
+                }
+                Ok(())
+            }
+            TypeRef::ImplTrait { bounds } => write!(f, "impl {}", bounds.join(" + ")),
+            TypeRef::TraitObject { bounds } => write!(f, "dyn {}", bounds.join(" + ")),
+            TypeRef::Other { tokens } => write!(f, "{}", tokens),
+        }
+    }
+}
+
+/// Whether a path is just segment names, with only type arguments and only
+/// on the last segment, so `TypeRef::Path` holds all of it.
+fn is_plain_path(path: &syn::Path) -> bool {
+    let Some(last) = path.segments.last() else {
+        return false;
+    };
+    let last_plain = match &last.arguments {
+        syn::PathArguments::None => true,
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .all(|a| matches!(a, syn::GenericArgument::Type(_))),
+        syn::PathArguments::Parenthesized(_) => false,
+    };
+    path.leading_colon.is_none()
+        && last_plain
+        && path
+            .segments
+            .iter()
+            .rev()
+            .skip(1)
+            .all(|s| s.arguments.is_none())
+}
+
+fn return_type(ret: &syn::ReturnType) -> TypeRef {
+    match ret {
+        syn::ReturnType::Type(_, ty) => TypeRef::from_syn(ty),
+        syn::ReturnType::Default => TypeRef::unit(),
+    }
+}
+
+fn bounds<'a>(bounds: impl Iterator<Item = &'a syn::TypeParamBound>) -> Vec<String> {
+    bounds.map(|b| b.to_token_stream().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(ty: &str) -> TypeRef {
+        TypeRef::from_syn(&syn::parse_str::<syn::Type>(ty).unwrap())
+    }
+
+    fn path(name: &str, args: Vec<TypeRef>) -> TypeRef {
+        TypeRef::Path {
+            path: vec![name.to_string()],
+            args,
+        }
+    }
+
+    #[test]
+    fn test_nested_generics() {
+        let ty = parse("Option<Vec<f32>>");
+        assert_eq!(
+            ty,
+            path("Option", vec![path("Vec", vec![path("f32", vec![])])])
+        );
+        assert_eq!(
+            ty.option_inner(),
+            Some(&path("Vec", vec![path("f32", vec![])]))
+        );
+        assert_eq!(ty.to_string(), "Option<Vec<f32>>");
+    }
+
+    #[test]
+    fn test_round_trips() {
+        for ty in [
+            "&'a mut u64",
+            "std::collections::HashMap<String, Vec<u8>>",
+            "(i32, f32)",
+            "(i32,)",
+            "()",
+            "&[&i32]",
+            "[u8; 4]",
+            "fn(i32, i32) -> i32",
+            "impl Iterator<Item = i32>",
+            "Box<dyn Fn(i32) -> i32 + Send>",
+        ] {
+            let parsed = parse(ty);
+            let reparsed = parse(&parsed.to_string());
+            assert_eq!(parsed, reparsed, "{}", ty);
+        }
+    }
+
+    #[test]
+    fn test_unusual_paths() {
+        // Kept whole as tokens rather than losing parts
+        for ty in [
+            "::std::vec::Vec<u8>",
+            "Cow<'a, str>",
+            "Foo<Item = u8>",
+            "a::B<u8>::C",
+            "Array<u8, 4>",
+        ] {
+            let parsed = parse(ty);
+            assert!(matches!(parsed, TypeRef::Other { .. }), "{}", ty);
+            assert_eq!(parsed.to_string().replace(' ', ""), ty.replace(' ', ""));
+        }
+        assert!(matches!(parse("std::vec::Vec<u8>"), TypeRef::Path { .. }));
+    }
+
+    #[test]
+    fn test_function_types() {
+        let test_fn = r#"
+            fn test_fn(a: u32, b: u64) -> &u128 {
+                0
+            }
+        "#;
+        let itemfn = syn::parse_str::<syn::ItemFn>(test_fn).unwrap();
+        let function_definition = crate::function_to_function_definition(&itemfn).unwrap();
+        assert_eq!(
+            function_definition.inputs[0].ty.type_ref,
+            Some(path("u32", vec![]))
+        );
+        assert_eq!(
+            function_definition.output.type_ref.unwrap().to_string(),
+            "&u128"
+        );
+    }
+
+    #[test]
+    fn test_decompose() {
+        let ty = parse("(i32, &str)");
+        let elements = ty.elements();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[1].dereferenced(), &path("str", vec![]));
+        assert!(parse("()").elements().is_empty());
+        assert_eq!(parse("i32").elements(), vec![&path("i32", vec![])]);
+    }
+
+    #[test]
+    fn test_json() {
+        let json = serde_json::to_value(parse("&mut Vec<u8>")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "reference",
+                "mutable": true,
+                "lifetime": null,
+                "elem": {
+                    "kind": "path",
+                    "path": ["Vec"],
+                    "args": [{"kind": "path", "path": ["u8"], "args": []}]
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,40 @@
+pub mod math;
+mod text;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+pub mod inline {
+    pub fn not_a_node() {}
+
+    pub mod deeper {
+        #[make_dynamicable()]
+        pub fn deep() -> i32 {
+            2
+        }
+    }
+}
+
+pub struct Counter;
+
+impl Counter {
+    #[make_dynamicable()]
+    pub fn start() -> i32 {
+        0
+    }
+
+    pub fn count(&self) -> i32 {
+        0
+    }
+
+    #[make_dynamicable()]
+    pub fn reset(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_one() {}
+}
@@ -0,0 +1,6 @@
+mod ops;
+
+#[ive_macros::make_dynamicable()]
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
@@ -0,0 +1,4 @@
+#[make_dynamicable()]
+pub fn negate(a: i32) -> i32 {
+    -a
+}
@@ -0,0 +1,6 @@
+#[path = "text_helpers.rs"]
+mod helpers;
+
+pub fn shout(s: &str) -> String {
+    s.to_uppercase()
+}
@@ -0,0 +1,3 @@
+pub fn whisper(s: &str) -> String {
+    s.to_lowercase()
+}
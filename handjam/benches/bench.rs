@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use handjam::{gentest::*};
-use ive::dyn_call::{DynLinearExec, box_dyn_call};
+use ive::dyn_call::{DynLinearExec, StorageMode, box_dyn_call};
 
 fn bench_chain(c: &mut Criterion) {
     let mut state = ChainState::default();
@@ -47,6 +47,35 @@ fn bench_dynamic(c: &mut Criterion) {
     }));
 }
 
+fn bench_dynamic_arena(c: &mut Criterion) {
+   let mut exec =  generate_linear_exec(CHAIN_LENGTH);
+   exec.set_storage_mode(StorageMode::Arena);
+
+    c.bench_function("chain_dynamic_arena", |b| b.iter(|| {
+        exec.set_runnable(0);
+         let count = exec.run().expect("Run should succeed");
+         assert_eq!(count,CHAIN_LENGTH);
+         assert_eq!(exec.value::<i32>(21).unwrap(),&21);
+         assert_eq!(exec.value::<i32>(9).unwrap(),&9);
+         assert_eq!(exec.value::<i32>(99).unwrap(),&99);
+    }));
+}
+
+fn bench_dynamic_validated(c: &mut Criterion) {
+   let mut exec =  generate_linear_exec(CHAIN_LENGTH);
+   exec.validate().expect("Chain should validate");
+   exec.set_checked(false);
+
+    c.bench_function("chain_dynamic_validated", |b| b.iter(|| {
+        exec.set_runnable(0);
+         let count = exec.run().expect("Run should succeed");
+         assert_eq!(count,CHAIN_LENGTH);
+         assert_eq!(exec.value::<i32>(21).unwrap(),&21);
+         assert_eq!(exec.value::<i32>(9).unwrap(),&9);
+         assert_eq!(exec.value::<i32>(99).unwrap(),&99);
+    }));
+}
+
 pub fn generate_linear_exec(count: usize) -> DynLinearExec {
     let nodes = (0..count - 1).map(|_| box_dyn_call(handjam::AddOneDynCall {}));
 
@@ -57,6 +86,6 @@ pub fn generate_linear_exec(count: usize) -> DynLinearExec {
     DynLinearExec::new_linear_chain(concat)
 }
 
-criterion_group!(benches, bench_chain, bench_straightchain, bench_dynamic);
+criterion_group!(benches, bench_chain, bench_straightchain, bench_dynamic, bench_dynamic_arena, bench_dynamic_validated);
 criterion_main!(benches);
 
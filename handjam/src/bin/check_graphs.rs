@@ -0,0 +1,30 @@
+use std::process::ExitCode;
+
+use handjam::compat::{check_graph_files, Builtins};
+use prototype_network::description::Library;
+
+const USAGE: &str = "Usage: check_graphs <library.json> <graph.json>...";
+
+/// Lists the saved graphs that don't work with a library, exiting with 1
+/// if there are any.
+fn main() -> anyhow::Result<ExitCode> {
+    let mut args = std::env::args().skip(1);
+    let library = args.next().ok_or_else(|| anyhow::anyhow!("{}", USAGE))?;
+    let graphs = args.collect::<Vec<_>>();
+
+    let json = std::fs::read_to_string(&library)
+        .map_err(|e| anyhow::anyhow!("Reading {}: {}", library, e))?;
+    let library = serde_json::from_str::<Library>(&json)?;
+
+    let affected = check_graph_files(&graphs, &library, &Builtins)?;
+    for graph in affected.iter() {
+        println!("{}:", graph.path.display());
+        for issue in graph.issues.iter() {
+            println!("    {}", issue);
+        }
+    }
+    match affected.is_empty() {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
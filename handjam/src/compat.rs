@@ -0,0 +1,447 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use ive::delay::{Delay, DELAY_KIND};
+use ive::dyn_call::{box_dyn_call, DynCall};
+use prototype_network::compat::Direction;
+use prototype_network::description::{Library, NodeDescriptor};
+
+use crate::descriptive_ive::{Id, Node, NodeFactory, PODGraph};
+
+/// Something in a graph that doesn't match the library it runs against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphIssue {
+    UnknownKind {
+        node: Id,
+        kind: String,
+    },
+    UnknownPort {
+        node: Id,
+        direction: Direction,
+        port: String,
+    },
+    /// A connection from a node that isn't in the graph
+    UnknownNode {
+        node: Id,
+        port: String,
+        from: Id,
+    },
+    /// A connection from an output of one type to an input of another
+    TypeMismatch {
+        node: Id,
+        port: String,
+        from: String,
+        to: String,
+    },
+    /// An input the library has that nothing connects to
+    MissingInput {
+        node: Id,
+        port: String,
+    },
+}
+
+impl fmt::Display for GraphIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphIssue::UnknownKind { node, kind } => {
+                write!(f, "node {} has unknown kind {}", node, kind)
+            }
+            GraphIssue::UnknownPort {
+                node,
+                direction,
+                port,
+            } => write!(f, "node {} has no {} {}", node, direction, port),
+            GraphIssue::UnknownNode { node, port, from } => write!(
+                f,
+                "input {} on node {} is connected to missing node {}",
+                port, node, from
+            ),
+            GraphIssue::TypeMismatch {
+                node,
+                port,
+                from,
+                to,
+            } => write!(
+                f,
+                "input {} on node {} takes {} but is connected to {}",
+                port, node, to, from
+            ),
+            GraphIssue::MissingInput { node, port } => {
+                write!(f, "input {} on node {} isn't connected", port, node)
+            }
+        }
+    }
+}
+
+/// A saved graph that won't work with a library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AffectedGraph {
+    pub path: PathBuf,
+    pub issues: Vec<GraphIssue>,
+}
+
+/// Creates the nodes ive provides itself, which aren't in any library.
+/// Only their ports are checked, so a delay is created for any value type.
+pub struct Builtins;
+
+impl NodeFactory for Builtins {
+    fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+        match node.kind.as_str() {
+            DELAY_KIND => Ok(box_dyn_call(Delay::<i32>::new(None))),
+            kind => anyhow::bail!("Unknown node kind {}", kind),
+        }
+    }
+}
+
+/// Checks that every node kind in the graph is in the library, that the
+/// connections use ports the library has with matching types, and that
+/// every input the library doesn't mark optional is connected.  Nodes that
+/// aren't in the library are created with `builtins`, and their
+/// connections are checked against their inputs and feedback inputs.
+///
+/// Types are compared by name without their paths, so `std::string::String`
+/// matches `String`, but an alias doesn't match what it stands for.  The
+/// types of built-in nodes' ports aren't checked.
+pub fn check_graph(
+    graph: &PODGraph,
+    library: &Library,
+    builtins: &impl NodeFactory,
+) -> Vec<GraphIssue> {
+    let find_node = |kind: &str| library.nodes().iter().find(|n| n.name() == kind);
+    let mut issues = vec![];
+
+    for node in graph.nodes.iter() {
+        for connection in node.incoming_connections.iter() {
+            if !graph.nodes.iter().any(|n| n.id == connection.from_id) {
+                issues.push(GraphIssue::UnknownNode {
+                    node: node.id.clone(),
+                    port: connection.to_port.clone(),
+                    from: connection.from_id.clone(),
+                });
+            }
+        }
+
+        let Some(descriptor) = find_node(&node.kind) else {
+            match builtins.create(node) {
+                Ok(call) => check_builtin(node, call.as_ref(), &mut issues),
+                Err(_) => issues.push(GraphIssue::UnknownKind {
+                    node: node.id.clone(),
+                    kind: node.kind.clone(),
+                }),
+            }
+            continue;
+        };
+
+        for connection in node.incoming_connections.iter() {
+            let Some(input) = descriptor
+                .inputs()
+                .iter()
+                .find(|p| p.name() == connection.to_port)
+            else {
+                issues.push(GraphIssue::UnknownPort {
+                    node: node.id.clone(),
+                    direction: Direction::Input,
+                    port: connection.to_port.clone(),
+                });
+                continue;
+            };
+
+            // Missing sources are reported above, and built-in ones have
+            // no types to compare
+            let from = graph.nodes.iter().find(|n| n.id == connection.from_id);
+            let Some(from_descriptor) = from.and_then(|n| find_node(&n.kind)) else {
+                continue;
+            };
+            match output(from_descriptor, &connection.from_port) {
+                Some(ty) if type_name(ty) != type_name(input.ty()) => {
+                    issues.push(GraphIssue::TypeMismatch {
+                        node: node.id.clone(),
+                        port: connection.to_port.clone(),
+                        from: ty.to_string(),
+                        to: input.ty().to_string(),
+                    })
+                }
+                Some(_) => {}
+                // The source node has no such output
+                None => issues.push(GraphIssue::UnknownPort {
+                    node: connection.from_id.clone(),
+                    direction: Direction::Output,
+                    port: connection.from_port.clone(),
+                }),
+            }
+        }
+
+        for input in descriptor.inputs().iter().filter(|i| !i.is_optional()) {
+            if !node
+                .incoming_connections
+                .iter()
+                .any(|c| c.to_port == input.name())
+            {
+                issues.push(GraphIssue::MissingInput {
+                    node: node.id.clone(),
+                    port: input.name().to_string(),
+                });
+            }
+        }
+    }
+    issues
+}
+
+fn check_builtin(node: &Node, call: &dyn DynCall, issues: &mut Vec<GraphIssue>) {
+    let inputs = call.inputs();
+    let feedback_inputs = call.feedback_inputs();
+    let ports = inputs.iter().chain(feedback_inputs.iter());
+    for connection in node.incoming_connections.iter() {
+        if !ports.clone().any(|p| p.name == connection.to_port) {
+            issues.push(GraphIssue::UnknownPort {
+                node: node.id.clone(),
+                direction: Direction::Input,
+                port: connection.to_port.clone(),
+            });
+        }
+    }
+    for port in ports.filter(|p| !p.optional && !p.variadic) {
+        if !node
+            .incoming_connections
+            .iter()
+            .any(|c| c.to_port == port.name)
+        {
+            issues.push(GraphIssue::MissingInput {
+                node: node.id.clone(),
+                port: port.name.to_string(),
+            });
+        }
+    }
+}
+
+/// Loads each saved graph and checks it against the library, returning the
+/// ones with issues.
+pub fn check_graph_files<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+    library: &Library,
+    builtins: &impl NodeFactory,
+) -> anyhow::Result<Vec<AffectedGraph>> {
+    let mut affected = vec![];
+    for path in paths {
+        let path = path.as_ref();
+        let issues = check_graph(&PODGraph::load(path)?, library, builtins);
+        if !issues.is_empty() {
+            affected.push(AffectedGraph {
+                path: path.to_path_buf(),
+                issues,
+            });
+        }
+    }
+    Ok(affected)
+}
+
+/// A type without spaces or the paths before any `::`, and without a
+/// leading `&`.
+fn type_name(ty: &str) -> String {
+    let mut name = String::new();
+    // Where the current path segment started
+    let mut segment = 0;
+    let mut chars = ty.trim().trim_start_matches('&').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                name.truncate(segment);
+            }
+            c if c.is_whitespace() => {}
+            c if c.is_alphanumeric() || c == '_' => name.push(c),
+            c => {
+                name.push(c);
+                segment = name.len();
+            }
+        }
+    }
+    name
+}
+
+fn output<'a>(node: &'a NodeDescriptor, port: &str) -> Option<&'a str> {
+    node.outputs()
+        .iter()
+        .find(|p| p.name() == port)
+        .map(|p| p.ty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::GraphBuilder;
+    use prototype_network::description::PortDescriptor;
+
+    fn library(add_inputs: &[(&str, &str)]) -> Library {
+        library_with(add_inputs, &[])
+    }
+
+    /// A library whose `add` node also has optional `extra` inputs
+    fn library_with(add_inputs: &[(&str, &str)], extra: &[&str]) -> Library {
+        let mut lib = Library::new("handjam", "handjam", "");
+        lib.add_node(NodeDescriptor::new(
+            "one",
+            "",
+            vec![],
+            vec![PortDescriptor::new("value", "", "i32")],
+        ));
+        let inputs = add_inputs
+            .iter()
+            .map(|(name, ty)| PortDescriptor::new(name, "", ty))
+            .chain(
+                extra
+                    .iter()
+                    .map(|name| PortDescriptor::new(name, "", "i32").with_optional(true)),
+            )
+            .collect();
+        lib.add_node(NodeDescriptor::new(
+            "add",
+            "",
+            inputs,
+            vec![PortDescriptor::new("value", "", "i32")],
+        ));
+        lib
+    }
+
+    fn graph() -> PODGraph {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        builder.build()
+    }
+
+    #[test]
+    fn test_compatible() {
+        let lib = library(&[("a", "i32"), ("b", "i32")]);
+        assert!(check_graph(&graph(), &lib, &Builtins).is_empty());
+    }
+
+    #[test]
+    fn test_optional_inputs() {
+        let lib = library_with(&[("a", "i32"), ("b", "i32")], &["by"]);
+        assert!(check_graph(&graph(), &lib, &Builtins).is_empty());
+    }
+
+    #[test]
+    fn test_type_paths() {
+        let lib = library(&[("a", "&std::primitive::i32"), ("b", "i32")]);
+        assert!(check_graph(&graph(), &lib, &Builtins).is_empty());
+        assert_eq!(
+            type_name("std::collections::HashMap<std::string::String, Vec<u8> >"),
+            "HashMap<String,Vec<u8>>"
+        );
+    }
+
+    #[test]
+    fn test_issues() {
+        let graph = graph();
+        let add = graph.nodes[1].id.clone();
+        let lib = library(&[("left", "i32"), ("b", "u32")]);
+        let issues = check_graph(&graph, &lib, &Builtins);
+        assert_eq!(
+            issues,
+            vec![
+                GraphIssue::UnknownPort {
+                    node: add.clone(),
+                    direction: Direction::Input,
+                    port: "a".to_string(),
+                },
+                GraphIssue::TypeMismatch {
+                    node: add.clone(),
+                    port: "b".to_string(),
+                    from: "i32".to_string(),
+                    to: "u32".to_string(),
+                },
+                GraphIssue::MissingInput {
+                    node: add,
+                    port: "left".to_string(),
+                },
+            ]
+        );
+
+        let mut lib = Library::new("empty", "empty", "");
+        lib.add_node(NodeDescriptor::new("add", "", vec![], vec![]));
+        let issues = check_graph(&graph, &lib, &Builtins);
+        assert_eq!(
+            issues[0],
+            GraphIssue::UnknownKind {
+                node: graph.nodes[0].id.clone(),
+                kind: "one".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_node() {
+        let lib = library(&[("a", "i32"), ("b", "i32")]);
+        let mut graph = graph();
+        let one = graph.nodes.remove(0);
+        let add = graph.nodes[0].id.clone();
+        assert_eq!(
+            check_graph(&graph, &lib, &Builtins),
+            vec![
+                GraphIssue::UnknownNode {
+                    node: add.clone(),
+                    port: "a".to_string(),
+                    from: one.id.clone(),
+                },
+                GraphIssue::UnknownNode {
+                    node: add,
+                    port: "b".to_string(),
+                    from: one.id,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_files() {
+        let dir = std::env::temp_dir().join(format!("handjam_compat_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("graph.json");
+        graph().save(&path).unwrap();
+
+        let compatible = library(&[("a", "i32"), ("b", "i32")]);
+        let broken = library(&[("a", "i32"), ("c", "i32")]);
+        let ok = check_graph_files([&path], &compatible, &Builtins).unwrap();
+        let affected = check_graph_files([&path], &broken, &Builtins).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(ok.is_empty());
+        assert_eq!(affected.len(), 1);
+        assert_eq!(affected[0].path, path);
+        assert_eq!(affected[0].issues.len(), 2);
+    }
+
+    #[test]
+    fn test_builtins() {
+        let lib = library(&[("a", "i32"), ("b", "i32")]);
+        let mut builder = GraphBuilder::new();
+        let mut delay = builder.add_node(DELAY_KIND);
+        let mut add = builder.add_node("add");
+        delay.out_port("value").connect_to(&add.in_port("a"));
+        delay.out_port("value").connect_to(&add.in_port("b"));
+        add.out_port("value").connect_to(&delay.in_port("input"));
+        let mut graph = builder.build();
+        assert!(check_graph(&graph, &lib, &Builtins).is_empty());
+
+        let delay = graph.nodes[0].id.clone();
+        graph.nodes[0].incoming_connections[0].to_port = "nope".into();
+        assert_eq!(
+            check_graph(&graph, &lib, &Builtins),
+            vec![
+                GraphIssue::UnknownPort {
+                    node: delay.clone(),
+                    direction: Direction::Input,
+                    port: "nope".to_string(),
+                },
+                GraphIssue::MissingInput {
+                    node: delay,
+                    port: "input".to_string(),
+                },
+            ]
+        );
+    }
+}
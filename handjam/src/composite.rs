@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+
+use crate::descriptive_ive::{Connection, Id, Node, NodeFactory, PODGraph};
+
+/// Ties a port on the outside of a composite to a port on a node inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposedPort {
+    pub name: String,
+    pub node_id: Id,
+    pub port: String,
+}
+
+impl ExposedPort {
+    pub fn new(name: &str, node_id: &Id, port: &str) -> Self {
+        Self {
+            name: name.into(),
+            node_id: node_id.clone(),
+            port: port.into(),
+        }
+    }
+}
+
+/// A reusable piece of graph that can be used as a single node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Composite {
+    pub graph: PODGraph,
+    /// Inner input ports fed by the composite's inputs.  The same name can
+    /// appear more than once to feed several inner ports from one input.
+    pub inputs: Vec<ExposedPort>,
+    /// Inner output ports that become the composite's outputs.
+    pub outputs: Vec<ExposedPort>,
+}
+
+impl Composite {
+    /// Checks every exposed port is on a node of the inner graph, and that
+    /// the node has that port.  Inner composites must already be registered.
+    fn check(
+        &self,
+        kind: &str,
+        registry: &CompositeRegistry,
+        factory: &impl NodeFactory,
+    ) -> anyhow::Result<()> {
+        let exposed = self
+            .inputs
+            .iter()
+            .map(|p| (p, true))
+            .chain(self.outputs.iter().map(|p| (p, false)));
+        for (port, is_input) in exposed {
+            let node = self
+                .graph
+                .nodes
+                .iter()
+                .find(|n| n.id == port.node_id)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Composite {} exposes port {} on missing node {}",
+                        kind,
+                        port.name,
+                        port.node_id
+                    )
+                })?;
+            let (inputs, outputs) = registry.ports(node, factory)?;
+            let found = if is_input { inputs } else { outputs };
+            if !found.contains(&port.port) {
+                bail!(
+                    "Composite {} exposes port {} as {}, which node {} ({}) doesn't have",
+                    kind,
+                    port.name,
+                    port.port,
+                    node.id,
+                    node.kind
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Composite definitions by node kind.  Any node in a graph whose kind is
+/// registered here gets replaced by the composite's inner graph when the
+/// graph is flattened.
+#[derive(Default)]
+pub struct CompositeRegistry {
+    composites: HashMap<String, Composite>,
+}
+
+impl CompositeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a composite after checking the ports it exposes.  The factory
+    /// creates the inner nodes that aren't composites themselves.
+    pub fn register(
+        &mut self,
+        kind: &str,
+        composite: Composite,
+        factory: impl NodeFactory,
+    ) -> anyhow::Result<()> {
+        composite.check(kind, self, &factory)?;
+        self.composites.insert(kind.into(), composite);
+        Ok(())
+    }
+
+    /// Returns the names of a node's input and output ports
+    fn ports(
+        &self,
+        node: &Node,
+        factory: &impl NodeFactory,
+    ) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+        let names = |ports: &[ExposedPort]| ports.iter().map(|p| p.name.clone()).collect();
+        if let Some(composite) = self.composites.get(&node.kind) {
+            return Ok((names(&composite.inputs), names(&composite.outputs)));
+        }
+        let call = factory.create(node)?;
+        let inputs = call
+            .inputs()
+            .iter()
+            .chain(call.feedback_inputs().iter())
+            .map(|p| p.name.to_string())
+            .collect();
+        let outputs = call.outputs().iter().map(|p| p.name.to_string()).collect();
+        Ok((inputs, outputs))
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&Composite> {
+        self.composites.get(kind)
+    }
+
+    /// Returns a copy of the graph with every composite node, including ones
+    /// nested inside other composites, replaced by its inner nodes.  Inner
+    /// node ids are prefixed with the id of the composite node and a `/`, so
+    /// a composite can be used many times in one graph.
+    pub fn flatten(&self, graph: &PODGraph) -> anyhow::Result<PODGraph> {
+        let mut graph = graph.clone();
+        // The composite kinds each node was expanded from, to catch a
+        // composite that contains itself.
+        let mut ancestry = vec![Vec::<String>::new(); graph.nodes.len()];
+
+        while let Some(index) = graph
+            .nodes
+            .iter()
+            .position(|n| self.composites.contains_key(&n.kind))
+        {
+            let kind = graph.nodes[index].kind.clone();
+            if ancestry[index].contains(&kind) {
+                bail!("Composite {} contains itself", kind);
+            }
+            let inner = self.expand(&mut graph, index)?;
+
+            let mut inner_ancestry = ancestry[index].clone();
+            inner_ancestry.push(kind);
+            let count = inner.len();
+            graph.nodes.splice(index..index + 1, inner);
+            ancestry.splice(index..index + 1, vec![inner_ancestry; count]);
+        }
+        Ok(graph)
+    }
+
+    /// Builds the inner nodes that replace the composite node at `index`, and
+    /// points every connection from the composite's outputs at the inner
+    /// nodes that produce them.
+    fn expand(&self, graph: &mut PODGraph, index: usize) -> anyhow::Result<Vec<Node>> {
+        let node = &graph.nodes[index];
+        let composite = &self.composites[&node.kind];
+        let prefix = format!("{}/", node.id);
+        let composite_id = node.id.clone();
+
+        for connection in node.incoming_connections.iter() {
+            if !composite
+                .inputs
+                .iter()
+                .any(|p| p.name == connection.to_port)
+            {
+                bail!(
+                    "Composite {} ({}) has no input {}",
+                    node.kind,
+                    node.id,
+                    connection.to_port
+                );
+            }
+        }
+
+        let inner = composite
+            .graph
+            .nodes
+            .iter()
+            .map(|inner| {
+                let mut incoming_connections = inner
+                    .incoming_connections
+                    .iter()
+                    .map(|c| Connection {
+                        from_id: format!("{}{}", prefix, c.from_id),
+                        from_port: c.from_port.clone(),
+                        to_port: c.to_port.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                // Whatever feeds the composite's inputs feeds the inner ports
+                for exposed in composite.inputs.iter().filter(|p| p.node_id == inner.id) {
+                    let outer = node
+                        .incoming_connections
+                        .iter()
+                        .filter(|c| c.to_port == exposed.name);
+                    incoming_connections.extend(outer.map(|c| Connection {
+                        from_id: c.from_id.clone(),
+                        from_port: c.from_port.clone(),
+                        to_port: exposed.port.clone(),
+                    }));
+                }
+                Node {
+                    id: format!("{}{}", prefix, inner.id),
+                    kind: inner.kind.clone(),
+                    incoming_connections,
+                    data: inner.data.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let kind = node.kind.clone();
+        for consumer in graph.nodes.iter_mut() {
+            for connection in consumer.incoming_connections.iter_mut() {
+                if connection.from_id != composite_id {
+                    continue;
+                }
+                let exposed = composite
+                    .outputs
+                    .iter()
+                    .find(|p| p.name == connection.from_port)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Composite {} ({}) has no output {}",
+                            kind,
+                            composite_id,
+                            connection.from_port
+                        )
+                    })?;
+                connection.from_id = format!("{}{}", prefix, exposed.node_id);
+                connection.from_port = exposed.port.clone();
+            }
+        }
+
+        Ok(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::{pod_to_sorted, sorted_to_exec, GraphBuilder};
+    use ive::dyn_call::{box_dyn_call, DynCall};
+
+    struct TestFactory;
+    impl NodeFactory for TestFactory {
+        fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+            match node.kind.as_str() {
+                "one" => Ok(box_dyn_call(crate::OneDynCall {})),
+                "two" => Ok(box_dyn_call(crate::TwoDynCall {})),
+                "add" => Ok(box_dyn_call(crate::AddDynCall {})),
+                "multiply" => Ok(box_dyn_call(crate::MultiplyDynCall {})),
+                _ => anyhow::bail!("Unknown node kind {}", node.kind),
+            }
+        }
+    }
+
+    /// x + 1
+    fn increment() -> Composite {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        let add_id = add.id();
+        Composite {
+            graph: builder.build(),
+            inputs: vec![ExposedPort::new("x", &add_id, "b")],
+            outputs: vec![ExposedPort::new("result", &add_id, "value")],
+        }
+    }
+
+    /// x * x + 1, using increment inside
+    fn square_plus_one() -> Composite {
+        let mut builder = GraphBuilder::new();
+        let square = builder.add_node("multiply");
+        let mut increment = builder.add_node("increment");
+        square.out_port("value").connect_to(&increment.in_port("x"));
+        let (square_id, increment_id) = (square.id(), increment.id());
+        Composite {
+            graph: builder.build(),
+            inputs: vec![
+                ExposedPort::new("x", &square_id, "a"),
+                ExposedPort::new("x", &square_id, "b"),
+            ],
+            outputs: vec![ExposedPort::new("result", &increment_id, "result")],
+        }
+    }
+
+    fn registry() -> CompositeRegistry {
+        let mut registry = CompositeRegistry::new();
+        registry
+            .register("increment", increment(), TestFactory)
+            .unwrap();
+        registry
+            .register("square_plus_one", square_plus_one(), TestFactory)
+            .unwrap();
+        registry
+    }
+
+    fn run(graph: &PODGraph) -> i32 {
+        let sorted = pod_to_sorted(graph).unwrap();
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        exec.run().unwrap();
+        let last = exec.output_slots(exec.len() - 1).start;
+        *exec.value::<i32>(last).unwrap()
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut builder = GraphBuilder::new();
+        let two = builder.add_node("two");
+        let mut first = builder.add_node("increment");
+        let mut second = builder.add_node("increment");
+        two.out_port("value").connect_to(&first.in_port("x"));
+        first.out_port("result").connect_to(&second.in_port("x"));
+        let (first_id, second_id) = (first.id(), second.id());
+
+        let flat = registry().flatten(&builder.build()).unwrap();
+        assert_eq!(flat.nodes.len(), 5);
+        assert!(flat.nodes.iter().all(|n| n.kind != "increment"));
+
+        let inner = flat.nodes[1].id.clone();
+        assert!(inner.starts_with(&format!("{}/", first_id)));
+        // The second composite's add reads from the first composite's add
+        let second_add = &flat.nodes[4];
+        assert!(second_add.id.starts_with(&format!("{}/", second_id)));
+        assert!(second_add.incoming_connections.contains(&Connection {
+            from_id: flat.nodes[2].id.clone(),
+            from_port: "value".into(),
+            to_port: "b".into(),
+        }));
+
+        assert_eq!(run(&flat), 4);
+    }
+
+    #[test]
+    fn test_flatten_nested() {
+        let mut builder = GraphBuilder::new();
+        let two = builder.add_node("two");
+        let mut square = builder.add_node("square_plus_one");
+        two.out_port("value").connect_to(&square.in_port("x"));
+
+        let flat = registry().flatten(&builder.build()).unwrap();
+        assert_eq!(flat.nodes.len(), 4);
+        assert_eq!(run(&flat), 5);
+    }
+
+    #[test]
+    fn test_flatten_errors() {
+        let mut builder = GraphBuilder::new();
+        let two = builder.add_node("two");
+        let mut inc = builder.add_node("increment");
+        two.out_port("value").connect_to(&inc.in_port("nope"));
+        assert!(registry().flatten(&builder.build()).is_err());
+
+        let mut builder = GraphBuilder::new();
+        let inc = builder.add_node("increment");
+        let mut add = builder.add_node("add");
+        inc.out_port("nope").connect_to(&add.in_port("a"));
+        assert!(registry().flatten(&builder.build()).is_err());
+
+        let mut registry = registry();
+        let mut builder = GraphBuilder::new();
+        builder.add_node("forever");
+        let forever = Composite {
+            graph: builder.build(),
+            inputs: vec![],
+            outputs: vec![],
+        };
+        registry.register("forever", forever, TestFactory).unwrap();
+        let mut builder = GraphBuilder::new();
+        builder.add_node("forever");
+        assert!(registry.flatten(&builder.build()).is_err());
+
+        let bad = Composite {
+            graph: PODGraph::default(),
+            inputs: vec![ExposedPort::new("x", &"missing".to_string(), "a")],
+            outputs: vec![],
+        };
+        assert!(registry.register("bad", bad, TestFactory).is_err());
+    }
+
+    #[test]
+    fn test_register_checks_ports() {
+        let mut registry = registry();
+        let mut bad = increment();
+        bad.inputs[0].port = "c".into();
+        let error = registry.register("bad", bad, TestFactory).err().unwrap();
+        assert!(error.to_string().contains("as c, which node"));
+
+        let mut bad = increment();
+        bad.outputs[0].port = "b".into();
+        assert!(registry.register("bad", bad, TestFactory).is_err());
+
+        // Ports on inner composites are the ones they expose
+        let mut bad = square_plus_one();
+        bad.outputs[0].port = "value".into();
+        assert!(registry.register("bad", bad, TestFactory).is_err());
+        assert!(registry.get("bad").is_none());
+    }
+}
@@ -1,27 +1,55 @@
-use anyhow::anyhow;
-use ive::dyn_call::{DynCall, DynLinearExec};
+use anyhow::{anyhow, bail};
+use ive::dyn_call::{DynCall, DynLinearExec, DynPort, UNCONNECTED};
+use ive::ports::{InputPort, NodeKind, OutputPort};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::marker::PhantomData;
+use std::path::Path;
 use std::rc::Rc;
 
-type Id = String;
-#[derive(Debug, PartialEq, Eq)]
+pub type Id = String;
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Connection {
     pub from_id: Id,
     pub from_port: String,
     pub to_port: String,
 }
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Node {
     pub id: Id,
     pub kind: String,
+    #[serde(default)]
     pub incoming_connections: Vec<Connection>,
+    #[serde(default)]
     pub data: Option<String>,
 }
-#[derive(Default, Debug, PartialEq, Eq)]
+/// A graph as plain data.  Saved graphs are its JSON.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PODGraph {
     pub nodes: Vec<Node>,
+    /// The version of each library the graph was saved against, by library
+    /// name.  See `migration`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub library_versions: BTreeMap<String, u32>,
 }
 impl PODGraph {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Reading {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| anyhow!("Parsing {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| anyhow!("Writing {}: {}", path.display(), e))
+    }
+
     fn node_index(&self, id: &Id) -> anyhow::Result<usize> {
         self.nodes
             .iter()
@@ -39,33 +67,213 @@ impl<'a> SortedGraph<'a> {
     pub fn iter(&'a self) -> impl Iterator<Item = &'a Node> {
         self.sort.iter().map(|i| &self.graph.nodes[*i])
     }
+    /// Returns where the node with the given id lands in the sorted order
+    pub fn node_position(&self, id: &Id) -> Option<usize> {
+        self.sort
+            .iter()
+            .position(|i| self.graph.nodes[*i].id == *id)
+    }
 }
 
-pub fn pod_to_sorted(graph: &PODGraph) -> anyhow::Result<SortedGraph> {
-    let mut g = petgraph::graph::Graph::<(), ()>::new();
+/// Edges point from the producing node to the consuming node, weighted with
+/// the consumer's index and the index of the connection within it.
+type ConnectionGraph = Graph<(), (usize, usize)>;
+
+/// Builds the graph used for sorting.  With a factory, connections into
+/// each node's feedback inputs are left out: they're read after the graph
+/// has run, so they can close a loop.
+fn pod_to_petgraph(
+    graph: &PODGraph,
+    factory: Option<&dyn NodeFactory>,
+) -> anyhow::Result<ConnectionGraph> {
+    let mut g = ConnectionGraph::new();
     let handles = graph
         .nodes
         .iter()
         .map(|_| g.add_node(()))
         .collect::<Vec<_>>();
     for (i, node) in graph.nodes.iter().enumerate() {
+        let feedback = match factory {
+            Some(factory) => factory.create(node)?.feedback_inputs(),
+            None => vec![],
+        };
         for (ci, connection) in node.incoming_connections.iter().enumerate() {
             let index = graph.node_index(&connection.from_id)?;
+            if feedback.iter().any(|p| p.name == connection.to_port) {
+                continue;
+            }
             let from = handles
                 .get(index)
                 .ok_or_else(|| anyhow!("Connection from index bad node {} connection {}", i, ci))?;
             let to = handles
                 .get(i)
                 .ok_or_else(|| anyhow!("bad handle index:  This one shouldn't ever happen"))?;
-            g.add_edge(*from, *to, ());
+            g.add_edge(*from, *to, (i, ci));
+        }
+    }
+    Ok(g)
+}
+
+/// One hop around a cycle: a node and the connection leading from it into
+/// the next node of the cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleStep {
+    pub id: Id,
+    pub kind: String,
+    pub to_id: Id,
+    pub connection: Connection,
+}
+
+/// The error `pod_to_sorted` fails with when the graph isn't acyclic.  The
+/// last step leads back into the first node.  Use
+/// `anyhow::Error::downcast_ref` to get at the steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    pub steps: Vec<CycleStep>,
+}
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cycle detected:")?;
+        for step in self.steps.iter() {
+            write!(
+                f,
+                " {} ({}) -[{} -> {}]->",
+                step.id, step.kind, step.connection.from_port, step.connection.to_port
+            )?;
+        }
+        if let Some(first) = self.steps.first() {
+            write!(f, " {}", first.id)?;
         }
+        Ok(())
     }
+}
 
-    let topo = petgraph::algo::toposort(&g, None).map_err(|_| anyhow!("Cycle detected"))?;
-    Ok(SortedGraph {
-        sort: topo.into_iter().map(|t| t.index()).collect::<Vec<_>>(),
-        graph,
-    })
+impl std::error::Error for CycleError {}
+
+impl CycleError {
+    fn find(graph: &PODGraph, g: &ConnectionGraph, start: NodeIndex) -> Self {
+        let steps = crate::graph::cycle_through(g, start)
+            .into_iter()
+            .map(|e| {
+                let (from, _) = g.edge_endpoints(e).unwrap();
+                let (to, ci) = g[e];
+                let node = &graph.nodes[from.index()];
+                CycleStep {
+                    id: node.id.clone(),
+                    kind: node.kind.clone(),
+                    to_id: graph.nodes[to].id.clone(),
+                    connection: graph.nodes[to].incoming_connections[ci].clone(),
+                }
+            })
+            .collect();
+        Self { steps }
+    }
+}
+
+/// Sorts the nodes so each comes after everything feeding it.  Of the nodes
+/// ready to go next, the one earliest in `graph.nodes` goes first, so the
+/// order only changes when the graph does.
+///
+/// Every connection counts, so a graph with feedback loops needs
+/// `pod_to_sorted_with_factory`.
+pub fn pod_to_sorted(graph: &PODGraph) -> anyhow::Result<SortedGraph<'_>> {
+    sort(graph, None)
+}
+
+/// Sorts like `pod_to_sorted`, but leaves out connections into the feedback
+/// inputs of the nodes `factory` creates, so those can close a loop.
+pub fn pod_to_sorted_with_factory<'a>(
+    graph: &'a PODGraph,
+    factory: &impl NodeFactory,
+) -> anyhow::Result<SortedGraph<'a>> {
+    sort(graph, Some(factory))
+}
+
+fn sort<'a>(
+    graph: &'a PODGraph,
+    factory: Option<&dyn NodeFactory>,
+) -> anyhow::Result<SortedGraph<'a>> {
+    let g = pod_to_petgraph(graph, factory)?;
+
+    let mut waiting = g
+        .node_indices()
+        .map(|n| g.neighbors_directed(n, Direction::Incoming).count())
+        .collect::<Vec<_>>();
+    let mut ready = g
+        .node_indices()
+        .filter(|n| waiting[n.index()] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut sort = Vec::with_capacity(graph.nodes.len());
+    while let Some(Reverse(n)) = ready.pop() {
+        sort.push(n.index());
+        // Neighbors come once per edge, matching how `waiting` was counted
+        for child in g.neighbors_directed(n, Direction::Outgoing) {
+            waiting[child.index()] -= 1;
+            if waiting[child.index()] == 0 {
+                ready.push(Reverse(child));
+            }
+        }
+    }
+
+    if sort.len() < graph.nodes.len() {
+        let cycle = petgraph::algo::toposort(&g, None)
+            .expect_err("Nodes were left unsorted without a cycle");
+        return Err(CycleError::find(graph, &g, cycle.node_id()).into());
+    }
+    Ok(SortedGraph { sort, graph })
+}
+
+/// Splits the graph into its strongly connected components, listing the
+/// node ids in each.  Any component with more than one node, or a node
+/// connected to itself, is a cycle that has to be broken before the graph
+/// can be sorted.
+pub fn strongly_connected_components(graph: &PODGraph) -> anyhow::Result<Vec<Vec<Id>>> {
+    components(graph, None, false)
+}
+
+/// The strongly connected components, leaving out connections into the
+/// feedback inputs of the nodes `factory` creates.
+pub fn strongly_connected_components_with_factory(
+    graph: &PODGraph,
+    factory: &impl NodeFactory,
+) -> anyhow::Result<Vec<Vec<Id>>> {
+    components(graph, Some(factory), false)
+}
+
+/// The strongly connected components of the graph that are cycles.
+pub fn cycles(graph: &PODGraph) -> anyhow::Result<Vec<Vec<Id>>> {
+    components(graph, None, true)
+}
+
+/// The cycles left once connections into the feedback inputs of the nodes
+/// `factory` creates are left out.
+pub fn cycles_with_factory(
+    graph: &PODGraph,
+    factory: &impl NodeFactory,
+) -> anyhow::Result<Vec<Vec<Id>>> {
+    components(graph, Some(factory), true)
+}
+
+fn components(
+    graph: &PODGraph,
+    factory: Option<&dyn NodeFactory>,
+    only_cycles: bool,
+) -> anyhow::Result<Vec<Vec<Id>>> {
+    let g = pod_to_petgraph(graph, factory)?;
+    Ok(petgraph::algo::tarjan_scc(&g)
+        .into_iter()
+        .filter(|component| {
+            !only_cycles || component.len() > 1 || g.contains_edge(component[0], component[0])
+        })
+        .map(|component| {
+            component
+                .into_iter()
+                .map(|i| graph.nodes[i.index()].id.clone())
+                .collect()
+        })
+        .collect())
 }
 
 type BuilderGraph = Rc<RefCell<PODGraph>>;
@@ -99,6 +307,9 @@ pub struct NodeBuilder {
     index: usize,
 }
 impl NodeBuilder {
+    pub fn id(&self) -> Id {
+        self.dag.borrow().nodes[self.index].id.clone()
+    }
     pub fn in_port(&mut self, port: &'static str) -> InPort {
         InPort {
             index: self.index,
@@ -114,8 +325,80 @@ impl NodeBuilder {
     }
 }
 
+/// The output port of a node added with `GraphBuilder::add`, which only
+/// connects to inputs taking a `T`.
+///
+/// ```compile_fail
+/// # use handjam::descriptive_ive::GraphBuilder;
+/// # use handjam::IntToStringDynCall;
+/// let mut builder = GraphBuilder::new();
+/// let mut to_string = builder.add::<IntToStringDynCall>();
+/// let mut other = builder.add::<IntToStringDynCall>();
+/// // A String output can't feed an i32 input
+/// to_string
+///     .output(IntToStringDynCall::value())
+///     .connect_to(&other.input(IntToStringDynCall::value_input()));
+/// ```
+pub struct TypedPortBuilder<T: ?Sized> {
+    port: PortBuilder,
+    ty: PhantomData<fn(&T)>,
+}
+impl<T: ?Sized> TypedPortBuilder<T> {
+    pub fn connect_to(&mut self, other: &TypedInPort<T>) -> &mut Self {
+        self.port.connect_to(&other.port);
+        self
+    }
+}
+pub struct TypedInPort<T: ?Sized> {
+    port: InPort,
+    ty: PhantomData<fn(&T)>,
+}
+
+/// A node added with `GraphBuilder::add`, whose ports come from the
+/// handles `make_dynamicable` generates on `N`.
+pub struct TypedNodeBuilder<N> {
+    node: NodeBuilder,
+    kind: PhantomData<fn(&N)>,
+}
+impl<N: NodeKind> TypedNodeBuilder<N> {
+    pub fn id(&self) -> Id {
+        self.node.id()
+    }
+    pub fn input<T: ?Sized>(&mut self, port: InputPort<N, T>) -> TypedInPort<T> {
+        TypedInPort {
+            port: self.node.in_port(port.name()),
+            ty: PhantomData,
+        }
+    }
+    pub fn output<T: ?Sized>(&self, port: OutputPort<N, T>) -> TypedPortBuilder<T> {
+        TypedPortBuilder {
+            port: self.node.out_port(port.name()),
+            ty: PhantomData,
+        }
+    }
+    /// The node without its type, for ports the handles don't cover
+    pub fn untyped(&mut self) -> &mut NodeBuilder {
+        &mut self.node
+    }
+}
+
+/// How `GraphBuilder::add_node` picks node ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeIds {
+    /// The kind and a count of that kind so far, e.g. `add_1`, `add_2`
+    #[default]
+    KindCounter,
+    /// Uuids from a generator with this seed, the same on every build
+    Seeded(u64),
+    /// Random uuids, different on every build
+    Random,
+}
+
 pub struct GraphBuilder {
     dag: BuilderGraph,
+    ids: NodeIds,
+    /// The state of the `NodeIds::Seeded` generator
+    seed: u64,
 }
 impl Default for GraphBuilder {
     fn default() -> Self {
@@ -123,16 +406,64 @@ impl Default for GraphBuilder {
     }
 }
 impl GraphBuilder {
+    /// A builder giving nodes `NodeIds::KindCounter` ids, so building the
+    /// same graph twice gives the same `PODGraph`.
     pub fn new() -> Self {
+        Self::with_ids(NodeIds::default())
+    }
+    pub fn with_ids(ids: NodeIds) -> Self {
+        let seed = match ids {
+            NodeIds::Seeded(seed) => seed,
+            _ => 0,
+        };
         Self {
             dag: Rc::new(RefCell::new(PODGraph::default())),
+            ids,
+            seed,
         }
     }
     pub fn add_node(&mut self, kind: &'static str) -> NodeBuilder {
+        let id = self.next_id(kind);
+        self.push(kind, id)
+    }
+    /// Adds a node with an id of your choosing.  Fails if it's taken.
+    pub fn add_node_with_id(
+        &mut self,
+        kind: &'static str,
+        id: impl Into<Id>,
+    ) -> anyhow::Result<NodeBuilder> {
+        let id = id.into();
+        if self.dag.borrow().node_index(&id).is_ok() {
+            bail!("Node id {} is already used", id);
+        }
+        Ok(self.push(kind, id))
+    }
+    /// Adds a node of a `make_dynamicable` type, for connecting with its
+    /// typed port handles.
+    pub fn add<N: NodeKind>(&mut self) -> TypedNodeBuilder<N> {
+        TypedNodeBuilder {
+            node: self.add_node(N::KIND),
+            kind: PhantomData,
+        }
+    }
+    pub fn add_with_id<N: NodeKind>(
+        &mut self,
+        id: impl Into<Id>,
+    ) -> anyhow::Result<TypedNodeBuilder<N>> {
+        Ok(TypedNodeBuilder {
+            node: self.add_node_with_id(N::KIND, id)?,
+            kind: PhantomData,
+        })
+    }
+    pub fn build(self) -> PODGraph {
+        self.dag.take()
+    }
+
+    fn push(&mut self, kind: &'static str, id: Id) -> NodeBuilder {
         let nodes = &mut self.dag.borrow_mut().nodes;
         let index = nodes.len();
         nodes.push(Node {
-            id: uuid::Uuid::new_v4().to_string(),
+            id,
             kind: kind.into(),
             incoming_connections: vec![],
             data: None,
@@ -142,15 +473,56 @@ impl GraphBuilder {
             index,
         }
     }
-    pub fn build(self) -> PODGraph {
-        self.dag.take()
+
+    fn next_id(&mut self, kind: &str) -> Id {
+        let dag = self.dag.clone();
+        let taken = |id: &Id| dag.borrow().node_index(id).is_ok();
+        match self.ids {
+            NodeIds::KindCounter => {
+                let count = dag.borrow().nodes.iter().filter(|n| n.kind == kind).count();
+                (count + 1..)
+                    .map(|i| format!("{}_{}", kind, i))
+                    .find(|id| !taken(id))
+                    .unwrap()
+            }
+            NodeIds::Seeded(_) => loop {
+                let bytes = [splitmix64(&mut self.seed), splitmix64(&mut self.seed)];
+                let mut random = [0; 16];
+                random[..8].copy_from_slice(&bytes[0].to_le_bytes());
+                random[8..].copy_from_slice(&bytes[1].to_le_bytes());
+                let id = uuid::Builder::from_random_bytes(random)
+                    .into_uuid()
+                    .to_string();
+                if !taken(&id) {
+                    break id;
+                }
+            },
+            NodeIds::Random => uuid::Uuid::new_v4().to_string(),
+        }
     }
 }
 
+/// A small, fast generator for reproducible ids
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
 pub trait NodeFactory {
     fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>>;
 }
+impl<F: NodeFactory> NodeFactory for &F {
+    fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+        (*self).create(node)
+    }
+}
 
+/// Builds an exec running the nodes in sorted order.  Each input reads the
+/// store slot of the output its connection names, and each node's children
+/// are the nodes reading from it.
 pub fn sorted_to_exec(
     sorted: &SortedGraph,
     factory: impl NodeFactory,
@@ -172,34 +544,125 @@ pub fn sorted_to_exec(
         }
     }
 
-    let exec = DynLinearExec::new(compute_nodes.into_iter());
-
-    //     // setup the connections
-    //     for ((i,node),computenode) in std::iter::zip(sorted.iter().enumerate(),compute_nodes.iter()) {
-    //         // for each real port:
-    //         let input_indices = computenode.inputs().iter().map(|p| {
-    //             // Look for an incoming connection
-    //             let connection = node
-    //                 .incoming_connections
-    //                 .iter()
-    //                 .find(|c| c.to_port == p.name)
-    //                 .ok_or_else(|| anyhow!("Missing connection for input {}", p.name))?;
-    //             // Find the output node
-    //             let from_position = sorted.node_position(&connection.from_id).ok_or_else(|| anyhow!("Could not find now for connection {}", p.name))?;
-    //             let from_port_index = 0; //compute_nodes[from_position].o XXX
-
-    //             Ok(output_indices[from_position] + from_port_index)
-    // //            let output_node = sorted.sorted_node(connection.from_index);
-    //         }).collect::<anyhow::Result<Vec<_>>>()?;
-    //         exec.inputs(i, input_indices);
-    //     }
+    // Finds the node and store slot feeding a connection
+    let source = |connection: &Connection, port: &str| -> anyhow::Result<_> {
+        let from_position = sorted
+            .node_position(&connection.from_id)
+            .ok_or_else(|| anyhow!("Could not find node for connection {}", port))?;
+        let from_port_index = compute_nodes[from_position]
+            .outputs()
+            .iter()
+            .position(|o| o.name == connection.from_port)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Could not find source port {} for connection {}",
+                    connection.from_port,
+                    port
+                )
+            })?;
+        Ok((
+            Some(from_position),
+            output_indices[from_position] + from_port_index,
+        ))
+    };
+    // Finds what feeds each port.  Optional ports can be left unconnected,
+    // and variadic ones take every connection to them, in order.  Any other
+    // port takes exactly one.
+    let wire = |node: &Node, ports: &[DynPort]| {
+        let mut wired = vec![];
+        for p in ports {
+            let mut connections = node
+                .incoming_connections
+                .iter()
+                .filter(|c| c.to_port == p.name);
+            if p.variadic {
+                for connection in connections {
+                    wired.push(source(connection, p.name)?);
+                }
+                continue;
+            }
+            match connections.next() {
+                Some(connection) => wired.push(source(connection, p.name)?),
+                None if p.optional => wired.push((None, UNCONNECTED)),
+                None => bail!(
+                    "Missing connection for input {} on node {}",
+                    p.name,
+                    node.id
+                ),
+            }
+            if connections.next().is_some() {
+                bail!(
+                    "Input {} on node {} has more than one connection",
+                    p.name,
+                    node.id
+                );
+            }
+        }
+        anyhow::Ok(wired)
+    };
+
+    // setup the connections
+    let mut input_indices = vec![];
+    let mut feedback_indices = vec![];
+    let mut children = vec![vec![]; compute_nodes.len()];
+    for (i, (node, computenode)) in sorted.iter().zip(compute_nodes.iter()).enumerate() {
+        let inputs = computenode.inputs();
+        let feedback_inputs = computenode.feedback_inputs();
+        if let Some(connection) = node.incoming_connections.iter().find(|c| {
+            !inputs
+                .iter()
+                .chain(feedback_inputs.iter())
+                .any(|p| p.name == c.to_port)
+        }) {
+            bail!(
+                "Node {} ({}) has no input {}",
+                node.id,
+                node.kind,
+                connection.to_port
+            );
+        }
+
+        // for each real port:
+        let indices = wire(node, &inputs)?
+            .into_iter()
+            .map(|(from_position, index)| {
+                if let Some(from_position) = from_position {
+                    if !children[from_position].contains(&i) {
+                        children[from_position].push(i);
+                    }
+                }
+                index
+            })
+            .collect();
+        input_indices.push(indices);
+
+        // Feedback ports are read after the run, so they don't make this
+        // node a child of whatever feeds them
+        let feedback = wire(node, &feedback_inputs)?;
+        feedback_indices.push(feedback.into_iter().map(|(_, index)| index).collect());
+    }
+
+    let mut exec = DynLinearExec::new(compute_nodes.into_iter());
+    for (i, ((indices, feedback), children)) in input_indices
+        .into_iter()
+        .zip(feedback_indices)
+        .zip(children)
+        .enumerate()
+    {
+        exec.inputs(i, indices);
+        exec.feedback(i, feedback);
+        exec.children(i, children);
+    }
 
+    // Checks the port types line up, and lets runs skip the per-node checks
+    exec.validate().map_err(|e| anyhow!("{}", e))?;
     Ok(exec)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ive::delay::{Delay, DELAY_KIND};
     use ive::dyn_call::box_dyn_call;
 
     #[test]
@@ -284,12 +747,31 @@ mod tests {
         assert_eq!(sorted.sort, vec![0, 1]);
     }
 
+    #[test]
+    fn test_save_load() {
+        let graph = make_test_graph();
+        let path = std::env::temp_dir().join(format!("handjam_graph_{}.json", std::process::id()));
+        graph.save(&path).unwrap();
+        let loaded = PODGraph::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, graph);
+
+        // Nodes without connections or data can leave them out
+        let json = r#"{"nodes": [{"id": "one", "kind": "one"}]}"#;
+        let graph = serde_json::from_str::<PODGraph>(json).unwrap();
+        assert!(graph.nodes[0].incoming_connections.is_empty());
+    }
+
     struct TestFactory;
     impl NodeFactory for TestFactory {
         fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
             match node.kind.as_str() {
                 "one" => Ok(box_dyn_call(crate::OneDynCall {})),
+                "two" => Ok(box_dyn_call(crate::TwoDynCall {})),
                 "add" => Ok(box_dyn_call(crate::AddDynCall {})),
+                "increment" => Ok(box_dyn_call(crate::IncrementDynCall {})),
+                "sum" => Ok(box_dyn_call(crate::SumDynCall {})),
+                DELAY_KIND => Ok(box_dyn_call(Delay::<i32>::parse(node.data.as_deref())?)),
                 _ => anyhow::bail!("Unknown node kind"),
             }
         }
@@ -307,8 +789,409 @@ mod tests {
         let sorted = pod_to_sorted(&graph).unwrap();
         assert_eq!(sorted.sort, vec![0, 1]);
 
-        let mut _exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
-        //  let count = exec.run().unwrap();
-        //  assert_eq!(count, 2);
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        let count = exec.run().unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(exec.value::<i32>(1).unwrap(), &2);
+
+        exec.set_runnable(0);
+        let count = exec.run().unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_node_position() {
+        let mut builder = GraphBuilder::new();
+        let mut add = builder.add_node("add");
+        let one = builder.add_node("one");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        let (add_id, one_id) = (add.id(), one.id());
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        assert_eq!(sorted.node_position(&one_id), Some(0));
+        assert_eq!(sorted.node_position(&add_id), Some(1));
+        assert_eq!(sorted.node_position(&"missing".to_string()), None);
+    }
+
+    #[test]
+    fn test_wiring() {
+        // Added consumers first, so the exec's order isn't the graph's
+        let mut builder = GraphBuilder::new();
+        let mut last = builder.add_node("add");
+        let mut add = builder.add_node("add");
+        let one = builder.add_node("one");
+        let two = builder.add_node("two");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        two.out_port("value").connect_to(&add.in_port("b"));
+        add.out_port("value").connect_to(&last.in_port("a"));
+        one.out_port("value").connect_to(&last.in_port("b"));
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        assert_eq!(sorted.sort, vec![2, 3, 1, 0]);
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        // Inputs read the store slots of the sorted producers
+        assert_eq!(exec.node_inputs(2), &[0, 1]);
+        assert_eq!(exec.node_inputs(3), &[2, 0]);
+        assert_eq!(exec.node_children(0), &[2, 3]);
+        assert_eq!(exec.node_children(1), &[2]);
+        assert_eq!(exec.node_children(2), &[3]);
+        assert!(exec.node_children(3).is_empty());
+        assert_eq!(exec.run().unwrap(), 4);
+        assert_eq!(exec.value::<i32>(3).unwrap(), &4);
+
+        // Rerunning a producer reruns everything downstream of it
+        exec.set_runnable(1);
+        assert_eq!(exec.run().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_missing_source_port() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("nope").connect_to(&add.in_port("b"));
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let error = sorted_to_exec(&sorted, TestFactory {}).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Could not find source port nope for connection b"
+        );
+    }
+
+    fn build_with(ids: NodeIds) -> PODGraph {
+        let mut builder = GraphBuilder::with_ids(ids);
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        let mut add_again = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        one.out_port("value").connect_to(&add_again.in_port("a"));
+        add.out_port("value").connect_to(&add_again.in_port("b"));
+        builder.build()
+    }
+
+    #[test]
+    fn test_deterministic_ids() {
+        let graph = build_with(NodeIds::KindCounter);
+        let ids = graph
+            .nodes
+            .iter()
+            .map(|n| n.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["one_1", "add_1", "add_2"]);
+        assert_eq!(graph, build_with(NodeIds::KindCounter));
+
+        assert_eq!(
+            build_with(NodeIds::Seeded(7)),
+            build_with(NodeIds::Seeded(7))
+        );
+        assert_ne!(
+            build_with(NodeIds::Seeded(7)),
+            build_with(NodeIds::Seeded(8))
+        );
+        assert_ne!(build_with(NodeIds::Random), build_with(NodeIds::Random));
+    }
+
+    #[test]
+    fn test_user_ids() {
+        let mut builder = GraphBuilder::new();
+        builder.add_node_with_id("one", "add_1").unwrap();
+        assert!(builder.add_node_with_id("one", "add_1").is_err());
+        // Generated ids step around the ones already taken
+        assert_eq!(builder.add_node("add").id(), "add_2");
+    }
+
+    #[test]
+    fn test_stable_sort() {
+        // Of the nodes ready to go, the earliest added goes first
+        let mut builder = GraphBuilder::new();
+        let mut add = builder.add_node("add");
+        let two = builder.add_node("two");
+        let one = builder.add_node("one");
+        builder.add_node("three");
+        two.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        let graph = builder.build();
+
+        let sorted = pod_to_sorted(&graph).unwrap();
+        assert_eq!(sorted.sort, vec![1, 2, 0, 3]);
+        let ids = sorted.iter().map(|n| n.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["two_1", "one_1", "add_1", "three_1"]);
+    }
+
+    #[test]
+    fn test_typed_builder() {
+        use crate::{AddDynCall, OneDynCall, SumDynCall};
+
+        let mut builder = GraphBuilder::new();
+        let one = builder.add::<OneDynCall>();
+        let mut add = builder.add::<AddDynCall>();
+        let mut sum = builder.add_with_id::<SumDynCall>("total").unwrap();
+        one.output(OneDynCall::value())
+            .connect_to(&add.input(AddDynCall::a()))
+            .connect_to(&add.input(AddDynCall::b()));
+        add.output(AddDynCall::value())
+            .connect_to(&sum.input(SumDynCall::values()));
+        one.output(OneDynCall::value())
+            .connect_to(&sum.input(SumDynCall::values()));
+        let typed = builder.build();
+
+        // The same graph as with kinds and port names
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        let mut sum = builder.add_node_with_id("sum", "total").unwrap();
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        add.out_port("value").connect_to(&sum.in_port("values"));
+        one.out_port("value").connect_to(&sum.in_port("values"));
+        assert_eq!(typed, builder.build());
+
+        let sorted = pod_to_sorted(&typed).unwrap();
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        exec.run().unwrap();
+        assert_eq!(exec.value::<i32>(2).unwrap(), &3);
+    }
+
+    #[test]
+    fn test_missing_connection() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        assert!(sorted_to_exec(&sorted, TestFactory {}).is_err());
+    }
+
+    #[test]
+    fn test_optional_input() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut by_default = builder.add_node("increment");
+        let mut by_one = builder.add_node("increment");
+        one.out_port("value").connect_to(&by_default.in_port("a"));
+        by_default
+            .out_port("value")
+            .connect_to(&by_one.in_port("a"));
+        by_default
+            .out_port("value")
+            .connect_to(&by_one.in_port("by"));
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        assert_eq!(exec.node_inputs(1), &[0, UNCONNECTED]);
+        assert_eq!(exec.node_children(1), &[2]);
+        assert_eq!(exec.run().unwrap(), 3);
+        assert_eq!(exec.value::<i32>(1).unwrap(), &2);
+        assert_eq!(exec.value::<i32>(2).unwrap(), &4);
+    }
+
+    #[test]
+    fn test_variadic_input() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        let mut sum = builder.add_node("sum");
+        // A variadic input with nothing connected gets an empty slice
+        builder.add_node("sum");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        add.out_port("value").connect_to(&sum.in_port("values"));
+        one.out_port("value").connect_to(&sum.in_port("values"));
+        add.out_port("value").connect_to(&sum.in_port("values"));
+
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        // In connection order
+        assert_eq!(exec.node_inputs(2), &[1, 0, 1]);
+        assert_eq!(exec.node_children(0), &[1, 2]);
+        assert!(exec.node_inputs(3).is_empty());
+        assert_eq!(exec.run().unwrap(), 4);
+        assert_eq!(exec.value::<i32>(2).unwrap(), &5);
+        assert_eq!(exec.value::<i32>(3).unwrap(), &0);
+    }
+
+    #[test]
+    fn test_bad_connections() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        let add_id = add.id();
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let error = sorted_to_exec(&sorted, TestFactory {}).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("Input a on node {} has more than one connection", add_id)
+        );
+
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+        one.out_port("value").connect_to(&add.in_port("c"));
+        let add_id = add.id();
+        let graph = builder.build();
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let error = sorted_to_exec(&sorted, TestFactory {}).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("Node {} (add) has no input c", add_id)
+        );
+    }
+
+    #[test]
+    fn test_cycle_error() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut first = builder.add_node("add");
+        let mut second = builder.add_node("add");
+        let mut last = builder.add_node("add");
+        one.out_port("value").connect_to(&first.in_port("a"));
+        first.out_port("value").connect_to(&second.in_port("a"));
+        second.out_port("value").connect_to(&first.in_port("b"));
+        one.out_port("value").connect_to(&second.in_port("b"));
+        second.out_port("value").connect_to(&last.in_port("a"));
+        second.out_port("value").connect_to(&last.in_port("b"));
+        let (first_id, second_id) = (first.id(), second.id());
+
+        let graph = builder.build();
+        let error = pod_to_sorted(&graph).err().unwrap();
+        let cycle = error.downcast_ref::<CycleError>().unwrap();
+        assert_eq!(cycle.steps.len(), 2);
+        let step = cycle.steps.iter().find(|s| s.id == second_id).unwrap();
+        assert_eq!(step.kind, "add");
+        assert_eq!(step.to_id, first_id);
+        assert_eq!(step.connection.to_port, "b");
+        assert!(error
+            .to_string()
+            .contains(&format!("{} (add) -[value -> b]-> {}", second_id, first_id)));
+
+        let found = cycles(&graph).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].len(), 2);
+        assert!(found[0].contains(&first_id) && found[0].contains(&second_id));
+        assert_eq!(strongly_connected_components(&graph).unwrap().len(), 3);
+        assert!(cycles(&make_test_graph()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_self_cycle() {
+        let mut builder = GraphBuilder::new();
+        let mut add = builder.add_node("add");
+        add.out_port("value").connect_to(&add.in_port("a"));
+        let graph = builder.build();
+
+        let error = pod_to_sorted(&graph).err().unwrap();
+        assert_eq!(error.downcast_ref::<CycleError>().unwrap().steps.len(), 1);
+        assert_eq!(
+            cycles(&graph).unwrap(),
+            vec![vec![graph.nodes[0].id.clone()]]
+        );
+    }
+
+    #[test]
+    fn test_delay_loop() {
+        // A running total: sum = previous sum + one
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut delay = builder.add_node(DELAY_KIND);
+        let mut sum = builder.add_node("add");
+        delay.out_port("value").connect_to(&sum.in_port("a"));
+        one.out_port("value").connect_to(&sum.in_port("b"));
+        sum.out_port("value").connect_to(&delay.in_port("input"));
+        let sum_id = sum.id();
+
+        let mut graph = builder.build();
+        graph.nodes[1].data = Some("10".into());
+        assert!(cycles_with_factory(&graph, &TestFactory)
+            .unwrap()
+            .is_empty());
+
+        let sorted = pod_to_sorted_with_factory(&graph, &TestFactory).unwrap();
+        let sum_index = sorted.node_position(&sum_id).unwrap();
+        let mut exec = sorted_to_exec(&sorted, TestFactory {}).unwrap();
+        for total in 11..14 {
+            exec.run().unwrap();
+            let slot = exec.output_slots(sum_index).start;
+            assert_eq!(exec.value::<i32>(slot).unwrap(), &total);
+        }
+
+        // The delay still needs its input connected
+        graph.nodes[1].incoming_connections.clear();
+        let sorted = pod_to_sorted_with_factory(&graph, &TestFactory).unwrap();
+        assert!(sorted_to_exec(&sorted, TestFactory {}).is_err());
+    }
+
+    #[test]
+    fn test_feedback_by_port() {
+        // What breaks the loop is the feedback input, not the delay's kind
+        struct Previous;
+        impl NodeFactory for Previous {
+            fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+                match node.kind.as_str() {
+                    "previous" => Ok(box_dyn_call(Delay::<i32>::new(Some(0)))),
+                    _ => TestFactory.create(node),
+                }
+            }
+        }
+
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut previous = builder.add_node("previous");
+        let mut sum = builder.add_node("add");
+        previous.out_port("value").connect_to(&sum.in_port("a"));
+        one.out_port("value").connect_to(&sum.in_port("b"));
+        sum.out_port("value").connect_to(&previous.in_port("input"));
+        let sum_id = sum.id();
+
+        let graph = builder.build();
+        assert!(cycles_with_factory(&graph, &Previous).unwrap().is_empty());
+        let sorted = pod_to_sorted_with_factory(&graph, &Previous).unwrap();
+        let mut exec = sorted_to_exec(&sorted, Previous).unwrap();
+        exec.run().unwrap();
+        exec.run().unwrap();
+        let slot = exec
+            .output_slots(sorted.node_position(&sum_id).unwrap())
+            .start;
+        assert_eq!(exec.value::<i32>(slot).unwrap(), &2);
+
+        // Through an ordinary input the same loop is a cycle
+        let mut graph = graph;
+        graph.nodes[1].kind = "increment".into();
+        graph.nodes[1].incoming_connections[0].to_port = "a".into();
+        let error = pod_to_sorted_with_factory(&graph, &Previous).err().unwrap();
+        assert_eq!(error.downcast_ref::<CycleError>().unwrap().steps.len(), 2);
+    }
+
+    #[test]
+    fn test_feedback_self_loop() {
+        // A delay fed its own output holds its initial value
+        let mut builder = GraphBuilder::new();
+        let mut delay = builder.add_node(DELAY_KIND);
+        delay.out_port("value").connect_to(&delay.in_port("input"));
+        let graph = builder.build();
+
+        assert!(cycles_with_factory(&graph, &TestFactory)
+            .unwrap()
+            .is_empty());
+        assert!(pod_to_sorted_with_factory(&graph, &TestFactory).is_ok());
+        // Without a factory it's an ordinary connection
+        assert_eq!(cycles(&graph).unwrap().len(), 1);
+        assert!(pod_to_sorted(&graph).is_err());
     }
 }
@@ -0,0 +1,146 @@
+use std::fmt::Write;
+
+use ive::dot::{escape, escape_record};
+use ive::dyn_call::DynPort;
+
+use crate::descriptive_ive::{NodeFactory, PODGraph};
+
+fn port_field(direction: &str, port: &DynPort) -> String {
+    format!(
+        "<{}_{}> {}: {}",
+        direction,
+        port.name,
+        escape_record(port.name),
+        escape_record(&port.kind.join(" "))
+    )
+}
+
+/// Renders a graph as a DOT digraph.  Each node is a record listing its
+/// kind with its input ports on the left and output ports on the right,
+/// using `factory` to look up the port names and types.
+pub fn pod_to_dot(graph: &PODGraph, factory: &impl NodeFactory) -> anyhow::Result<String> {
+    let calls = graph
+        .nodes
+        .iter()
+        .map(|node| factory.create(node))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut out = String::new();
+    _ = writeln!(out, "digraph {{");
+    _ = writeln!(out, "    rankdir=LR;");
+    _ = writeln!(out, "    node [shape=record];");
+    for (node, call) in graph.nodes.iter().zip(calls.iter()) {
+        let inputs = call.inputs();
+        let outputs = call.outputs();
+
+        let mut fields = vec![];
+        if !inputs.is_empty() {
+            let ports = inputs
+                .iter()
+                .map(|p| port_field("in", p))
+                .collect::<Vec<_>>();
+            fields.push(format!("{{{}}}", ports.join("|")));
+        }
+        let mut title = escape_record(&node.kind);
+        if let Some(data) = &node.data {
+            title += &format!("\\n{}", escape_record(data));
+        }
+        fields.push(title);
+        if !outputs.is_empty() {
+            let ports = outputs
+                .iter()
+                .map(|p| port_field("out", p))
+                .collect::<Vec<_>>();
+            fields.push(format!("{{{}}}", ports.join("|")));
+        }
+        _ = writeln!(
+            out,
+            "    \"{}\" [label=\"{{{}}}\"];",
+            escape(&node.id),
+            fields.join("|")
+        );
+    }
+    for (node, call) in graph.nodes.iter().zip(calls.iter()) {
+        let inputs = call.inputs();
+        for connection in node.incoming_connections.iter() {
+            let from = graph
+                .nodes
+                .iter()
+                .position(|n| n.id == connection.from_id)
+                .ok_or_else(|| anyhow::anyhow!("Node {} not found", connection.from_id))?;
+            let from_known = calls[from]
+                .outputs()
+                .iter()
+                .any(|p| p.name == connection.from_port);
+            let to_known = inputs.iter().any(|p| p.name == connection.to_port);
+
+            let mut line = format!("    \"{}\"", escape(&connection.from_id));
+            if from_known {
+                line += &format!(":out_{}", connection.from_port);
+            }
+            line += &format!(" -> \"{}\"", escape(&node.id));
+            if to_known {
+                line += &format!(":in_{}", connection.to_port);
+            }
+            if !from_known || !to_known {
+                // Point out connections to ports the node doesn't have
+                line += &format!(
+                    " [label=\"{} -> {}\", color=red]",
+                    escape(&connection.from_port),
+                    escape(&connection.to_port)
+                );
+            }
+            _ = writeln!(out, "{};", line);
+        }
+    }
+    _ = writeln!(out, "}}");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::{GraphBuilder, Node};
+    use ive::dyn_call::{box_dyn_call, DynCall};
+
+    struct TestFactory;
+    impl NodeFactory for TestFactory {
+        fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+            match node.kind.as_str() {
+                "one" => Ok(box_dyn_call(crate::OneDynCall {})),
+                "add" => Ok(box_dyn_call(crate::AddDynCall {})),
+                _ => anyhow::bail!("Unknown node kind"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_pod_to_dot() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("c"));
+        let graph = builder.build();
+        let (one, add) = (&graph.nodes[0].id, &graph.nodes[1].id);
+
+        let dot = pod_to_dot(&graph, &TestFactory {}).unwrap();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains(&format!(
+            "\"{}\" [label=\"{{{{<in_a> a: i32|<in_b> b: i32}}|add|{{<out_value> value: i32}}}}\"];",
+            add
+        )));
+        assert!(dot.contains(&format!("\"{}\":out_value -> \"{}\":in_a;", one, add)));
+        assert!(dot.contains(&format!(
+            "\"{}\":out_value -> \"{}\" [label=\"value -> c\", color=red];",
+            one, add
+        )));
+    }
+
+    #[test]
+    fn test_pod_to_dot_unknown_kind() {
+        let mut builder = GraphBuilder::new();
+        builder.add_node("nope");
+        assert!(pod_to_dot(&builder.build(), &TestFactory {}).is_err());
+    }
+}
@@ -1,6 +1,9 @@
+use ive::dot::escape;
 use petgraph::graph::NodeIndex;
 use petgraph::prelude::*;
 use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write;
 use std::rc::Rc;
 
 pub struct PortBuilder<NODETYPE, PORTTYPE> {
@@ -68,15 +71,103 @@ impl<NODETYPE, PORTTYPE> GraphBuilder<NODETYPE, PORTTYPE> {
     pub fn build(self) -> Graph<NODETYPE, (PORTTYPE, PORTTYPE)> {
         self.dag.take()
     }
-    pub fn sort(self) -> Result<Vec<NodeIndex>,Box<dyn std::error::Error>> {
+    /// Renders the graph as a DOT digraph, labeling each edge with the
+    /// ports it connects.
+    pub fn to_dot(&self) -> String
+    where
+        NODETYPE: std::fmt::Display,
+        PORTTYPE: std::fmt::Display,
+    {
+        let dag = self.dag.borrow();
+        let mut out = String::from("digraph {\n");
+        for index in dag.node_indices() {
+            _ = writeln!(
+                out,
+                "    {} [label=\"{}\"];",
+                index.index(),
+                escape(&dag[index].to_string())
+            );
+        }
+        for edge in dag.edge_references() {
+            let (from_port, to_port) = edge.weight();
+            _ = writeln!(
+                out,
+                "    {} -> {} [label=\"{} -> {}\"];",
+                edge.source().index(),
+                edge.target().index(),
+                escape(&from_port.to_string()),
+                escape(&to_port.to_string())
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+    /// Splits the graph into its strongly connected components.  Components
+    /// with more than one node, or a node connected to itself, are cycles.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeIndex>> {
+        petgraph::algo::tarjan_scc(&*self.dag.borrow())
+    }
+    /// Sorts the graph, or fails listing every node and port pair around the
+    /// first cycle found.
+    pub fn sort(self) -> Result<Vec<NodeIndex>,Box<dyn std::error::Error>>
+    where
+        NODETYPE: std::fmt::Display,
+        PORTTYPE: std::fmt::Display,
+    {
         let dag = self.dag.take();
 
-        let sorted = petgraph::algo::toposort(&dag, None).map_err(|_| "Cycle detected")?;
+        let sorted = petgraph::algo::toposort(&dag, None).map_err(|cycle| {
+            let mut message = String::from("Cycle detected:");
+            for edge in cycle_through(&dag, cycle.node_id()) {
+                let (from, _) = dag.edge_endpoints(edge).unwrap();
+                let (from_port, to_port) = &dag[edge];
+                _ = write!(message, " {} -[{} -> {}]->", dag[from], from_port, to_port);
+            }
+            _ = write!(message, " {}", dag[cycle.node_id()]);
+            message
+        })?;
     
         Ok(sorted)
     }
 }
 
+/// The edges of the shortest cycle that starts and ends at `start`, found by
+/// walking edges breadth first until one leads back to it.  Empty if `start`
+/// isn't on a cycle.
+pub fn cycle_through<N, E>(graph: &Graph<N, E>, start: NodeIndex) -> Vec<EdgeIndex> {
+    let mut came_from = vec![None; graph.node_count()];
+    let mut queue = VecDeque::from([start]);
+    let mut last = None;
+    'search: while let Some(current) = queue.pop_front() {
+        for edge in graph.edges(current) {
+            let target = edge.target();
+            if came_from[target.index()].is_some() {
+                continue;
+            }
+            came_from[target.index()] = Some(edge.id());
+            if target == start {
+                last = Some(edge.id());
+                break 'search;
+            }
+            queue.push_back(target);
+        }
+    }
+
+    let mut cycle = vec![];
+    let mut edge = last;
+    while let Some(e) = edge {
+        cycle.push(e);
+        let (from, _) = graph.edge_endpoints(e).unwrap();
+        edge = if from == start {
+            None
+        } else {
+            came_from[from.index()]
+        };
+    }
+    cycle.reverse();
+    cycle
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -155,11 +246,11 @@ mod tests {
             });
         });
 
-        assert!(incoming_edges.get("A").is_none());
-        assert!(incoming_edges.get("B").is_some());
-        assert!(incoming_edges.get("C").is_some());
-        assert!(incoming_edges.get("D").is_some());
-        assert!(incoming_edges.get("E").is_some());
+        assert!(!incoming_edges.contains_key("A"));
+        assert!(incoming_edges.contains_key("B"));
+        assert!(incoming_edges.contains_key("C"));
+        assert!(incoming_edges.contains_key("D"));
+        assert!(incoming_edges.contains_key("E"));
 
         assert!(incoming_edges.get("B").unwrap() == &(a, ("One", "Two")));
     }
@@ -204,6 +295,20 @@ mod tests {
         assert!(sorted_names == expected || sorted_names == expected2);
     }
 
+    #[test]
+    fn test_to_dot() {
+        let mut builder = GraphBuilder::<&str, &str>::new();
+
+        let mut a = builder.add_node("A");
+        let b = builder.add_node("B");
+        a.out_port("outport").connect_to(b.in_port("inport"));
+
+        assert_eq!(
+            builder.to_dot(),
+            "digraph {\n    0 [label=\"A\"];\n    1 [label=\"B\"];\n    0 -> 1 [label=\"outport -> inport\"];\n}\n"
+        );
+    }
+
     #[test]
     fn test_cycles_fail() {
         let mut builder = GraphBuilder::<&str, &str>::new();
@@ -219,4 +324,33 @@ mod tests {
 
         assert!(sorted.is_err());
     }
+
+    #[test]
+    fn test_cycle_error() {
+        let mut builder = GraphBuilder::<&str, &str>::new();
+
+        let mut a = builder.add_node("A");
+        let mut b = builder.add_node("B");
+        let mut c = builder.add_node("C");
+        let d = builder.add_node("D");
+
+        a.out_port("out").connect_to(b.in_port("in"));
+        b.out_port("out").connect_to(c.in_port("in"));
+        c.out_port("out").connect_to(b.in_port("back"));
+        c.out_port("out").connect_to(d.in_port("in"));
+
+        let components = builder.strongly_connected_components();
+        let cycles = components
+            .iter()
+            .filter(|c| c.len() > 1)
+            .collect::<Vec<_>>();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].len(), 2);
+
+        let error = builder.sort().unwrap_err().to_string();
+        assert!(
+            error == "Cycle detected: B -[out -> in]-> C -[out -> back]-> B"
+                || error == "Cycle detected: C -[out -> back]-> B -[out -> in]-> C"
+        );
+    }
 }
@@ -1,13 +1,18 @@
-#![feature(type_alias_impl_trait)] // So using type aliases for impl Trait works without headaches
 //use ive_macros::make_dynamicable;
 
 use ive_macros::make_dynamicable;
 
 pub mod macros;
+pub mod compat;
+pub mod composite;
 pub mod gentest;
 pub mod graph;
 pub mod descriptive_ive;
+pub mod dot;
+pub mod mermaid;
 pub mod linear_execution;
+pub mod live;
+pub mod migration;
 
 #[make_dynamicable()]
 pub fn zero() -> i32 {
@@ -51,6 +56,16 @@ pub fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
+#[make_dynamicable()]
+pub fn increment(a: i32, #[default = 1] by: i32) -> i32 {
+    a + by
+}
+
+#[make_dynamicable()]
+pub fn sum(values: &[&i32]) -> i32 {
+    values.iter().copied().sum()
+}
+
 #[make_dynamicable()]
 pub fn multiply(a: i32, b: i32) -> i32 {
     a * b
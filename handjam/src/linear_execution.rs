@@ -1,8 +1,10 @@
 use crate::descriptive_ive::{SortedGraph, Node};
 
+#[allow(dead_code)]
 trait StoreAllocation {
     fn kind(&self) -> &str;
 }
+#[allow(dead_code)]
 trait ExecutionNode {
     type IndexIterator: Iterator<Item = usize>;
     fn kind(&self) -> &str;
@@ -27,6 +29,7 @@ pub trait NodeDatabase {
     fn lookup<'a>(&self, kind: &str) -> anyhow::Result<NodeDesc<'a>>;
 }
 
+#[allow(dead_code)]
 struct FakeDB;
 impl NodeDatabase for FakeDB {
     fn lookup<'a>(&self, kind: &str) -> anyhow::Result<NodeDesc<'a>> {
@@ -0,0 +1,505 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail};
+use ive::dyn_call::{DirtyEnum, DynLinearExec};
+
+use crate::descriptive_ive::{
+    pod_to_sorted_with_factory, sorted_to_exec, Connection, Id, Node, NodeFactory, PODGraph,
+};
+
+/// A graph together with the executor built from it, which can be edited
+/// without losing cached results.
+///
+/// Edits are collected until `commit`, which re-sorts the graph and builds a
+/// new executor.  Nodes whose upstream subgraph wasn't touched by any edit
+/// keep their values and run state, so the next `run` only computes the
+/// edited nodes and what depends on them.  Nodes with feedback inputs keep
+/// state of their own that a rebuild loses, so they start over along with
+/// what depends on them.  If the edited graph can't be built, `commit`
+/// fails and the current executor is left as it was.
+pub struct LiveExec<F: NodeFactory> {
+    graph: PODGraph,
+    factory: F,
+    exec: DynLinearExec,
+    /// Node id for each executor index.
+    ids: Vec<Id>,
+    /// Nodes edited since the last commit.
+    changed: HashSet<Id>,
+}
+
+impl<F: NodeFactory> LiveExec<F> {
+    pub fn new(graph: PODGraph, factory: F) -> anyhow::Result<Self> {
+        let (exec, ids) = build(&graph, &factory)?;
+        Ok(Self {
+            graph,
+            factory,
+            exec,
+            ids,
+            changed: HashSet::new(),
+        })
+    }
+
+    /// The graph including edits that haven't been committed yet.
+    pub fn graph(&self) -> &PODGraph {
+        &self.graph
+    }
+    pub fn exec(&self) -> &DynLinearExec {
+        &self.exec
+    }
+    pub fn exec_mut(&mut self) -> &mut DynLinearExec {
+        &mut self.exec
+    }
+    pub fn run(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.exec.run()
+    }
+    /// Computes only what's needed to bring one node up to date.
+    pub fn evaluate(&mut self, id: &Id) -> Result<usize, Box<dyn std::error::Error>> {
+        let index = self
+            .index_of(id)
+            .ok_or_else(|| format!("Node {} not found", id))?;
+        self.exec.evaluate(index)
+    }
+
+    /// Where a node is in the committed executor.
+    pub fn index_of(&self, id: &Id) -> Option<usize> {
+        self.ids.iter().position(|i| i == id)
+    }
+    pub fn value<T>(&self, id: &Id) -> Result<&T, Box<dyn std::error::Error>>
+    where
+        T: 'static + std::any::Any,
+    {
+        let index = self
+            .index_of(id)
+            .ok_or_else(|| format!("Node {} not found", id))?;
+        let slots = self.exec.output_slots(index);
+        if slots.is_empty() {
+            return Err(format!("Node {} has no output", id).into());
+        }
+        self.exec.value::<T>(slots.start)
+    }
+    pub fn run_state(&self, id: &Id) -> Option<DirtyEnum> {
+        self.index_of(id).map(|i| self.exec.run_state(i))
+    }
+
+    fn node_mut(&mut self, id: &Id) -> anyhow::Result<&mut Node> {
+        self.graph
+            .nodes
+            .iter_mut()
+            .find(|n| n.id == *id)
+            .ok_or_else(|| anyhow!("Node {} not found", id))
+    }
+
+    pub fn add_node(&mut self, node: Node) -> anyhow::Result<()> {
+        if self.graph.nodes.iter().any(|n| n.id == node.id) {
+            bail!("Node {} already exists", node.id);
+        }
+        self.changed.insert(node.id.clone());
+        self.graph.nodes.push(node);
+        Ok(())
+    }
+
+    /// Removes a node along with every connection coming out of it.
+    pub fn remove_node(&mut self, id: &Id) -> anyhow::Result<()> {
+        let index = self
+            .graph
+            .nodes
+            .iter()
+            .position(|n| n.id == *id)
+            .ok_or_else(|| anyhow!("Node {} not found", id))?;
+        self.graph.nodes.remove(index);
+        self.changed.remove(id);
+        for node in self.graph.nodes.iter_mut() {
+            let before = node.incoming_connections.len();
+            node.incoming_connections.retain(|c| c.from_id != *id);
+            if node.incoming_connections.len() != before {
+                self.changed.insert(node.id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects into a node, replacing whatever was connected to that port.
+    pub fn connect(&mut self, to_id: &Id, connection: Connection) -> anyhow::Result<()> {
+        if !self.graph.nodes.iter().any(|n| n.id == connection.from_id) {
+            bail!("Node {} not found", connection.from_id);
+        }
+        let node = self.node_mut(to_id)?;
+        node.incoming_connections
+            .retain(|c| c.to_port != connection.to_port);
+        node.incoming_connections.push(connection);
+        self.changed.insert(to_id.clone());
+        Ok(())
+    }
+
+    /// Adds a connection without replacing anything, for variadic ports
+    /// that take several.
+    pub fn add_connection(&mut self, to_id: &Id, connection: Connection) -> anyhow::Result<()> {
+        if !self.graph.nodes.iter().any(|n| n.id == connection.from_id) {
+            bail!("Node {} not found", connection.from_id);
+        }
+        self.node_mut(to_id)?.incoming_connections.push(connection);
+        self.changed.insert(to_id.clone());
+        Ok(())
+    }
+
+    /// Removes every connection to a port.
+    pub fn disconnect(&mut self, to_id: &Id, to_port: &str) -> anyhow::Result<()> {
+        let node = self.node_mut(to_id)?;
+        let before = node.incoming_connections.len();
+        node.incoming_connections.retain(|c| c.to_port != to_port);
+        if node.incoming_connections.len() == before {
+            bail!("Node {} has no connection to {}", to_id, to_port);
+        }
+        self.changed.insert(to_id.clone());
+        Ok(())
+    }
+
+    pub fn set_data(&mut self, id: &Id, data: Option<String>) -> anyhow::Result<()> {
+        self.node_mut(id)?.data = data;
+        self.changed.insert(id.clone());
+        Ok(())
+    }
+
+    /// Rebuilds the executor from the edited graph, carrying over the values
+    /// and run state of every node that isn't downstream of an edit, along
+    /// with the executor's settings.
+    pub fn commit(&mut self) -> anyhow::Result<()> {
+        let (mut exec, ids) = build(&self.graph, &self.factory)?;
+        exec.set_storage_mode(self.exec.storage_mode());
+        exec.set_checked(self.exec.is_checked());
+
+        // Everything downstream of an edit needs computing again, as does
+        // everything downstream of a new node with feedback, which doesn't
+        // have the old one's state
+        let mut invalid = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                self.changed.contains(id) || !exec.node_call(i).feedback_inputs().is_empty()
+            })
+            .collect::<Vec<_>>();
+        for i in 0..invalid.len() {
+            if invalid[i] {
+                for child in exec.node_children(i).to_vec() {
+                    invalid[child] = true;
+                }
+            }
+        }
+
+        // Work out what carries over before touching the current executor,
+        // so it's left as it was if anything doesn't fit
+        let mut carried = vec![];
+        for (new_index, id) in ids.iter().enumerate() {
+            if invalid[new_index] {
+                continue;
+            }
+            let Some(old_index) = self.index_of(id) else {
+                continue;
+            };
+            let old_slots = self.exec.output_slots(old_index);
+            // A value moved into its consumer is gone, so make it again
+            if old_slots.clone().any(|s| self.exec.is_moved(s)) {
+                continue;
+            }
+            if old_slots.len() != exec.output_slots(new_index).len() {
+                bail!(
+                    "Node {} had {} outputs and now has {}",
+                    id,
+                    old_slots.len(),
+                    exec.output_slots(new_index).len()
+                );
+            }
+            carried.push((new_index, old_index));
+        }
+
+        for (new_index, old_index) in carried {
+            let values = self.exec.take_values(old_index);
+            exec.put_values(new_index, values)
+                .map_err(|e| anyhow!("Node {}: {}", ids[new_index], e))?;
+            exec.set_run_state(new_index, self.exec.run_state(old_index));
+        }
+
+        self.exec = exec;
+        self.ids = ids;
+        self.changed.clear();
+        Ok(())
+    }
+}
+
+fn build(graph: &PODGraph, factory: &impl NodeFactory) -> anyhow::Result<(DynLinearExec, Vec<Id>)> {
+    let sorted = pod_to_sorted_with_factory(graph, factory)?;
+    let exec = sorted_to_exec(&sorted, factory)?;
+    let ids = sorted.iter().map(|n| n.id.clone()).collect();
+    Ok((exec, ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::GraphBuilder;
+    use ive::delay::{Delay, DELAY_INPUT, DELAY_KIND};
+    use ive::dyn_call::{
+        box_dyn_call, DynCall, DynCallResult, DynPort, InputGetter, OutputSetter, StorageMode,
+    };
+    use ive_macros::make_dynamicable;
+    use std::cell::Cell;
+
+    #[make_dynamicable()]
+    fn discard(a: i32) {
+        let _ = a;
+    }
+
+    /// A node with however many outputs it was made with, all set to 1
+    struct Outputs(usize);
+    impl DynCall for Outputs {
+        fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+            for i in 0..self.0 {
+                outputs.some(i, 1);
+            }
+            Ok(())
+        }
+        fn kind(&self) -> &'static str {
+            "outputs"
+        }
+        fn input_len(&self) -> usize {
+            0
+        }
+        fn output_len(&self) -> usize {
+            self.0
+        }
+        fn inputs(&self) -> Vec<DynPort> {
+            vec![]
+        }
+        fn output_type(&self) -> &'static [&'static str] {
+            &["i32"]
+        }
+    }
+
+    /// Makes `outputs` nodes with `count` outputs
+    struct Resizing {
+        count: Cell<usize>,
+    }
+    impl NodeFactory for Resizing {
+        fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+            match node.kind.as_str() {
+                "outputs" => Ok(box_dyn_call(Outputs(self.count.get()))),
+                _ => TestFactory {}.create(node),
+            }
+        }
+    }
+
+    struct TestFactory;
+    impl NodeFactory for TestFactory {
+        fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+            match node.kind.as_str() {
+                "one" => Ok(box_dyn_call(crate::OneDynCall {})),
+                "two" => Ok(box_dyn_call(crate::TwoDynCall {})),
+                "add" => Ok(box_dyn_call(crate::AddDynCall {})),
+                "add_one" => Ok(box_dyn_call(crate::AddOneDynCall {})),
+                "sum" => Ok(box_dyn_call(crate::SumDynCall {})),
+                "discard" => Ok(box_dyn_call(DiscardDynCall {})),
+                DELAY_KIND => Ok(box_dyn_call(Delay::<i32>::parse(node.data.as_deref())?)),
+                _ => anyhow::bail!("Unknown node kind {}", node.kind),
+            }
+        }
+    }
+
+    fn connection(from: &Id, to_port: &str) -> Connection {
+        Connection {
+            from_id: from.clone(),
+            from_port: "value".into(),
+            to_port: to_port.into(),
+        }
+    }
+
+    fn node(id: &str, kind: &str) -> Node {
+        Node {
+            id: id.into(),
+            kind: kind.into(),
+            incoming_connections: vec![],
+            data: None,
+        }
+    }
+
+    // one -> add_one -> add_one
+    //   two -> add_one
+    fn live() -> (LiveExec<TestFactory>, Vec<Id>) {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut a = builder.add_node("add_one");
+        let mut b = builder.add_node("add_one");
+        let two = builder.add_node("two");
+        let mut c = builder.add_node("add_one");
+        one.out_port("value").connect_to(&a.in_port("a"));
+        a.out_port("value").connect_to(&b.in_port("a"));
+        two.out_port("value").connect_to(&c.in_port("a"));
+        let ids = vec![one.id(), a.id(), b.id(), two.id(), c.id()];
+
+        let mut live = LiveExec::new(builder.build(), TestFactory {}).unwrap();
+        assert_eq!(live.run().unwrap(), 5);
+        (live, ids)
+    }
+
+    #[test]
+    fn test_connect_keeps_upstream() {
+        let (mut live, ids) = live();
+        assert_eq!(live.value::<i32>(&ids[2]).unwrap(), &3);
+
+        // Feed the end of the first chain from two instead
+        live.connect(&ids[2], connection(&ids[3], "a")).unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.run_state(&ids[0]), Some(DirtyEnum::Clean));
+        assert_eq!(live.run_state(&ids[2]), Some(DirtyEnum::NeedCompute));
+        assert_eq!(live.value::<i32>(&ids[1]).unwrap(), &2);
+
+        assert_eq!(live.run().unwrap(), 1);
+        assert_eq!(live.value::<i32>(&ids[2]).unwrap(), &3);
+        assert_eq!(live.value::<i32>(&ids[4]).unwrap(), &3);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let (mut live, ids) = live();
+        live.set_data(&ids[0], None).unwrap();
+        live.set_data(&ids[3], None).unwrap();
+        live.commit().unwrap();
+
+        assert_eq!(live.evaluate(&ids[4]).unwrap(), 2);
+        assert_eq!(live.run_state(&ids[1]), Some(DirtyEnum::NeedCompute));
+        assert_eq!(live.run().unwrap(), 3);
+        assert!(live.evaluate(&"missing".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_add_and_remove_nodes() {
+        let (mut live, ids) = live();
+
+        let sum = "sum".to_string();
+        live.add_node(node(&sum, "add")).unwrap();
+        // Not connected yet, so it can't be committed
+        assert!(live.commit().is_err());
+        live.connect(&sum, connection(&ids[2], "a")).unwrap();
+        live.connect(&sum, connection(&ids[4], "b")).unwrap();
+        live.commit().unwrap();
+
+        assert_eq!(live.run().unwrap(), 1);
+        assert_eq!(live.value::<i32>(&sum).unwrap(), &6);
+
+        // Swap out the middle of the first chain
+        live.remove_node(&ids[1]).unwrap();
+        live.connect(&ids[2], connection(&ids[0], "a")).unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.index_of(&ids[1]), None);
+        assert_eq!(live.run().unwrap(), 2);
+        assert_eq!(live.value::<i32>(&sum).unwrap(), &5);
+    }
+
+    #[test]
+    fn test_add_connection() {
+        let (mut live, ids) = live();
+
+        let sum = "sum".to_string();
+        live.add_node(node(&sum, "sum")).unwrap();
+        live.add_connection(&sum, connection(&ids[2], "values"))
+            .unwrap();
+        live.add_connection(&sum, connection(&ids[4], "values"))
+            .unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.run().unwrap(), 1);
+        assert_eq!(live.value::<i32>(&sum).unwrap(), &6);
+
+        live.add_connection(&sum, connection(&ids[0], "values"))
+            .unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.run().unwrap(), 1);
+        assert_eq!(live.value::<i32>(&sum).unwrap(), &7);
+    }
+
+    #[test]
+    fn test_failed_commit_keeps_exec() {
+        let (mut live, ids) = live();
+
+        live.disconnect(&ids[4], "a").unwrap();
+        assert!(live.commit().is_err());
+        assert_eq!(live.value::<i32>(&ids[4]).unwrap(), &3);
+        assert_eq!(live.run().unwrap(), 0);
+
+        assert!(live.disconnect(&ids[4], "a").is_err());
+        assert!(live.add_node(node(&ids[0], "one")).is_err());
+    }
+
+    #[test]
+    fn test_failed_transfer_keeps_exec() {
+        let factory = Resizing {
+            count: Cell::new(1),
+        };
+        let mut graph = PODGraph::default();
+        graph.nodes.push(node("outputs", "outputs"));
+        let mut live = LiveExec::new(graph, &factory).unwrap();
+        assert_eq!(live.run().unwrap(), 1);
+
+        // The node wasn't edited, but now has a different number of outputs
+        factory.count.set(2);
+        live.add_node(node("two", "two")).unwrap();
+        assert!(live.commit().is_err());
+        assert_eq!(live.value::<i32>(&"outputs".to_string()).unwrap(), &1);
+    }
+
+    #[test]
+    fn test_value_without_outputs() {
+        let (mut live, ids) = live();
+        let discard = "discard".to_string();
+        live.add_node(node(&discard, "discard")).unwrap();
+        live.connect(&discard, connection(&ids[4], "a")).unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.run().unwrap(), 1);
+
+        let err = live.value::<i32>(&discard).unwrap_err();
+        assert_eq!(err.to_string(), "Node discard has no output");
+    }
+
+    #[test]
+    fn test_commit_keeps_settings() {
+        let (mut live, ids) = live();
+        live.exec_mut().set_storage_mode(StorageMode::Arena);
+        let checked = !live.exec().is_checked();
+        live.exec_mut().set_checked(checked);
+
+        live.set_data(&ids[3], None).unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.exec().storage_mode(), StorageMode::Arena);
+        assert_eq!(live.exec().is_checked(), checked);
+    }
+
+    #[test]
+    fn test_commit_restarts_feedback() {
+        // A running total starting from 10
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut delay = builder.add_node(DELAY_KIND);
+        let mut total = builder.add_node("add");
+        delay.out_port("value").connect_to(&total.in_port("a"));
+        one.out_port("value").connect_to(&total.in_port("b"));
+        total
+            .out_port("value")
+            .connect_to(&delay.in_port(DELAY_INPUT));
+        let (delay, total) = (delay.id(), total.id());
+        let mut graph = builder.build();
+        graph.nodes[1].data = Some("10".into());
+
+        let mut live = LiveExec::new(graph, TestFactory {}).unwrap();
+        for _ in 0..3 {
+            live.run().unwrap();
+        }
+        assert_eq!(live.value::<i32>(&total).unwrap(), &13);
+
+        // The new delay starts from its initial value, so the total does too
+        live.add_node(node("two", "two")).unwrap();
+        live.commit().unwrap();
+        assert_eq!(live.run_state(&delay), Some(DirtyEnum::NeedCompute));
+        assert_eq!(live.run_state(&total), Some(DirtyEnum::NeedCompute));
+        live.run().unwrap();
+        assert_eq!(live.value::<i32>(&delay).unwrap(), &10);
+        assert_eq!(live.value::<i32>(&total).unwrap(), &11);
+    }
+}
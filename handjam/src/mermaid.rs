@@ -0,0 +1,72 @@
+use std::fmt::Write;
+
+use prototype_network::render::mermaid_escape as escape;
+
+use crate::descriptive_ive::PODGraph;
+
+/// Renders a graph as a Mermaid flowchart.  Nodes are labeled with their
+/// kind and edges with the ports they connect.
+pub fn pod_to_mermaid(graph: &PODGraph) -> anyhow::Result<String> {
+    let mut out = String::new();
+    _ = writeln!(out, "flowchart LR");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label = match &node.data {
+            Some(data) => format!("{}<br/>{}", escape(&node.kind), escape(data)),
+            None => escape(&node.kind),
+        };
+        _ = writeln!(out, "    n{}[\"{}\"]", i, label);
+    }
+    for (i, node) in graph.nodes.iter().enumerate() {
+        for connection in node.incoming_connections.iter() {
+            let from = graph
+                .nodes
+                .iter()
+                .position(|n| n.id == connection.from_id)
+                .ok_or_else(|| anyhow::anyhow!("Node {} not found", connection.from_id))?;
+            _ = writeln!(
+                out,
+                "    n{} -->|\"{} → {}\"| n{}",
+                from,
+                escape(&connection.from_port),
+                escape(&connection.to_port),
+                i
+            );
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::GraphBuilder;
+
+    #[test]
+    fn test_pod_to_mermaid() {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add = builder.add_node("add");
+        one.out_port("value").connect_to(&add.in_port("a"));
+        one.out_port("value").connect_to(&add.in_port("b"));
+
+        let mermaid = pod_to_mermaid(&builder.build()).unwrap();
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n    n0[\"one\"]\n    n1[\"add\"]\n    n0 -->|\"value → a\"| n1\n    n0 -->|\"value → b\"| n1\n"
+        );
+    }
+
+    #[test]
+    fn test_escaped_data() {
+        let mut builder = GraphBuilder::new();
+        builder.add_node("const");
+        let mut graph = builder.build();
+        graph.nodes[0].data = Some("<b>\"x\"</b>".to_string());
+
+        let mermaid = pod_to_mermaid(&graph).unwrap();
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n    n0[\"const<br/>#lt;b#gt;#quot;x#quot;#lt;/b#gt;\"]\n"
+        );
+    }
+}
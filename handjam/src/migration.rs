@@ -0,0 +1,455 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::anyhow;
+use prototype_network::compat::Direction;
+use serde::{Deserialize, Serialize};
+
+use crate::descriptive_ive::{Connection, Id, Node, PODGraph};
+
+/// How to bring graphs saved against older versions of a library up to
+/// date.  Each graph records the library versions it was saved against in
+/// `PODGraph::library_versions`, and only newer migrations are applied.
+///
+/// Saved as JSON, e.g.
+/// `{"library": "sample_nodes", "migrations": [{"version": 1, "rules":
+/// [{"rule": "rename_kind", "from": "add_u32", "to": "add"}]}]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationTable {
+    pub library: String,
+    /// In increasing version order
+    pub migrations: Vec<Migration>,
+}
+
+/// The rules that take graphs to `version`, applied in order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Migration {
+    pub version: u32,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    RenameKind {
+        from: String,
+        to: String,
+    },
+    /// Renames a port on nodes of `kind`, as it's called at this point in
+    /// the migration
+    RenamePort {
+        kind: String,
+        direction: Direction,
+        from: String,
+        to: String,
+    },
+    /// Feeds a new input on nodes of `kind` that don't have it connected
+    /// from a new node holding the value
+    DefaultInput {
+        kind: String,
+        port: String,
+        value: DefaultValue,
+    },
+}
+
+/// A node that provides a value, like a constant with the value in `data`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultValue {
+    pub kind: String,
+    /// The output the value comes from
+    pub port: String,
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// Something a migration changed in a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rewrite {
+    KindRenamed {
+        node: Id,
+        from: String,
+        to: String,
+    },
+    PortRenamed {
+        node: Id,
+        direction: Direction,
+        from: String,
+        to: String,
+    },
+    DefaultAdded {
+        node: Id,
+        port: String,
+        /// The node added to hold the value
+        value_node: Id,
+    },
+}
+
+impl fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rewrite::KindRenamed { node, from, to } => {
+                write!(f, "node {}: renamed kind {} to {}", node, from, to)
+            }
+            Rewrite::PortRenamed {
+                node,
+                direction,
+                from,
+                to,
+            } => write!(f, "node {}: renamed {} {} to {}", node, direction, from, to),
+            Rewrite::DefaultAdded {
+                node,
+                port,
+                value_node,
+            } => write!(f, "node {}: fed input {} from {}", node, port, value_node),
+        }
+    }
+}
+
+/// What migrating a graph did, for one library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub library: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub rewrites: Vec<Rewrite>,
+}
+
+impl MigrationReport {
+    pub fn is_empty(&self) -> bool {
+        self.rewrites.is_empty()
+    }
+}
+
+impl MigrationTable {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Reading {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| anyhow!("Parsing {}: {}", path.display(), e))
+    }
+
+    /// The version graphs are at after migrating
+    pub fn latest(&self) -> u32 {
+        self.migrations.last().map_or(0, |m| m.version)
+    }
+
+    /// Marks a graph as up to date, for saving a graph built against the
+    /// current library.
+    pub fn stamp(&self, graph: &mut PODGraph) {
+        graph
+            .library_versions
+            .insert(self.library.clone(), self.latest());
+    }
+
+    /// Applies the migrations newer than the graph's version of the library,
+    /// then stamps it.  Graphs with no version get every migration.  If a
+    /// rule fails the graph is left as it was.
+    pub fn apply(&self, graph: &mut PODGraph) -> anyhow::Result<MigrationReport> {
+        if let Some(pair) = self
+            .migrations
+            .windows(2)
+            .find(|pair| pair[0].version >= pair[1].version)
+        {
+            return Err(anyhow!(
+                "Migrations for {} are out of order at version {}",
+                self.library,
+                pair[1].version
+            ));
+        }
+
+        let from_version = graph
+            .library_versions
+            .get(&self.library)
+            .copied()
+            .unwrap_or(0);
+        // Rules change the graph as they go, so work on a copy
+        let mut migrated = graph.clone();
+        let mut rewrites = vec![];
+        for migration in self.migrations.iter() {
+            if migration.version <= from_version {
+                continue;
+            }
+            for rule in migration.rules.iter() {
+                apply_rule(&mut migrated, rule, &mut rewrites)?;
+            }
+        }
+        let to_version = from_version.max(self.latest());
+        migrated
+            .library_versions
+            .insert(self.library.clone(), to_version);
+        *graph = migrated;
+
+        Ok(MigrationReport {
+            library: self.library.clone(),
+            from_version,
+            to_version,
+            rewrites,
+        })
+    }
+}
+
+/// Loads a saved graph and brings it up to date with every table.
+pub fn load_migrated(
+    path: impl AsRef<Path>,
+    tables: &[MigrationTable],
+) -> anyhow::Result<(PODGraph, Vec<MigrationReport>)> {
+    let mut graph = PODGraph::load(path)?;
+    let reports = tables
+        .iter()
+        .map(|table| table.apply(&mut graph))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((graph, reports))
+}
+
+fn apply_rule(
+    graph: &mut PODGraph,
+    rule: &Rule,
+    rewrites: &mut Vec<Rewrite>,
+) -> anyhow::Result<()> {
+    match rule {
+        Rule::RenameKind { from, to } => {
+            for node in graph.nodes.iter_mut().filter(|n| n.kind == *from) {
+                node.kind = to.clone();
+                rewrites.push(Rewrite::KindRenamed {
+                    node: node.id.clone(),
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+        Rule::RenamePort {
+            kind,
+            direction: Direction::Input,
+            from,
+            to,
+        } => {
+            for node in graph.nodes.iter_mut().filter(|n| n.kind == *kind) {
+                let mut renamed = false;
+                for connection in node.incoming_connections.iter_mut() {
+                    if connection.to_port == *from {
+                        connection.to_port = to.clone();
+                        renamed = true;
+                    }
+                }
+                if renamed {
+                    rewrites.push(Rewrite::PortRenamed {
+                        node: node.id.clone(),
+                        direction: Direction::Input,
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+            }
+        }
+        Rule::RenamePort {
+            kind,
+            direction: Direction::Output,
+            from,
+            to,
+        } => {
+            let ids = graph
+                .nodes
+                .iter()
+                .filter(|n| n.kind == *kind)
+                .map(|n| n.id.clone())
+                .collect::<Vec<_>>();
+            for id in ids {
+                let mut renamed = false;
+                let connections = graph
+                    .nodes
+                    .iter_mut()
+                    .flat_map(|n| n.incoming_connections.iter_mut())
+                    .filter(|c| c.from_id == id && c.from_port == *from);
+                for connection in connections {
+                    connection.from_port = to.clone();
+                    renamed = true;
+                }
+                if renamed {
+                    rewrites.push(Rewrite::PortRenamed {
+                        node: id,
+                        direction: Direction::Output,
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+            }
+        }
+        Rule::DefaultInput { kind, port, value } => {
+            let mut added = vec![];
+            for node in graph.nodes.iter_mut().filter(|n| n.kind == *kind) {
+                if node.incoming_connections.iter().any(|c| c.to_port == *port) {
+                    continue;
+                }
+                let value_node = format!("{}/{}", node.id, port);
+                node.incoming_connections.push(Connection {
+                    from_id: value_node.clone(),
+                    from_port: value.port.clone(),
+                    to_port: port.clone(),
+                });
+                rewrites.push(Rewrite::DefaultAdded {
+                    node: node.id.clone(),
+                    port: port.clone(),
+                    value_node: value_node.clone(),
+                });
+                added.push(Node {
+                    id: value_node,
+                    kind: value.kind.clone(),
+                    incoming_connections: vec![],
+                    data: value.data.clone(),
+                });
+            }
+            for node in added {
+                if graph.nodes.iter().any(|n| n.id == node.id) {
+                    return Err(anyhow!("Node {} already exists", node.id));
+                }
+                graph.nodes.push(node);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptive_ive::{pod_to_sorted, sorted_to_exec, GraphBuilder, NodeFactory};
+    use ive::dyn_call::{box_dyn_call, DynCall};
+
+    /// `sample` v1 renamed `add_one` to `inc` and its input `value` to `a`, v2
+    /// gave `add` a new input `b` fed by `one`.
+    fn table() -> MigrationTable {
+        serde_json::from_str(
+            r#"{
+                "library": "sample",
+                "migrations": [
+                    {"version": 1, "rules": [
+                        {"rule": "rename_kind", "from": "add_one", "to": "inc"},
+                        {"rule": "rename_port", "kind": "inc", "direction": "input",
+                         "from": "value", "to": "a"},
+                        {"rule": "rename_port", "kind": "one", "direction": "output",
+                         "from": "out", "to": "value"}
+                    ]},
+                    {"version": 2, "rules": [
+                        {"rule": "default_input", "kind": "add", "port": "b",
+                         "value": {"kind": "one", "port": "value"}}
+                    ]}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    /// A graph from before v1: `one -> add_one -> add`, with `add.b` unset.
+    fn old_graph() -> PODGraph {
+        let mut builder = GraphBuilder::new();
+        let one = builder.add_node("one");
+        let mut add_one = builder.add_node("add_one");
+        let mut add = builder.add_node("add");
+        one.out_port("out").connect_to(&add_one.in_port("value"));
+        add_one.out_port("value").connect_to(&add.in_port("a"));
+        builder.build()
+    }
+
+    struct Factory;
+    impl NodeFactory for Factory {
+        fn create(&self, node: &Node) -> anyhow::Result<Box<dyn DynCall>> {
+            match node.kind.as_str() {
+                "one" => Ok(box_dyn_call(crate::OneDynCall {})),
+                "add" => Ok(box_dyn_call(crate::AddDynCall {})),
+                "inc" => Ok(box_dyn_call(crate::IncrementDynCall {})),
+                kind => anyhow::bail!("Unknown node kind {}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut graph = old_graph();
+        let ids = graph.nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        let report = table().apply(&mut graph).unwrap();
+
+        assert_eq!((report.from_version, report.to_version), (0, 2));
+        let rewrites = report
+            .rewrites
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rewrites,
+            vec![
+                format!("node {}: renamed kind add_one to inc", ids[1]),
+                format!("node {}: renamed input value to a", ids[1]),
+                format!("node {}: renamed output out to value", ids[0]),
+                format!("node {}: fed input b from {}/b", ids[2], ids[2]),
+            ]
+        );
+        assert_eq!(graph.library_versions["sample"], 2);
+
+        // inc's `by` defaults to 1, so (1 + 1) + 1
+        let sorted = pod_to_sorted(&graph).unwrap();
+        let mut exec = sorted_to_exec(&sorted, Factory).unwrap();
+        exec.run().unwrap();
+        let add = sorted.node_position(&ids[2]).unwrap();
+        assert_eq!(*exec.value::<i32>(add).unwrap(), 3);
+
+        // Already up to date, so nothing more happens
+        let again = table().apply(&mut graph).unwrap();
+        assert!(again.is_empty());
+        assert_eq!((again.from_version, again.to_version), (2, 2));
+    }
+
+    #[test]
+    fn test_partial() {
+        let mut graph = old_graph();
+        graph.library_versions.insert("sample".to_string(), 1);
+        let report = table().apply(&mut graph).unwrap();
+        assert_eq!(report.rewrites.len(), 1);
+        assert!(matches!(report.rewrites[0], Rewrite::DefaultAdded { .. }));
+        assert_eq!(graph.nodes[1].kind, "add_one");
+    }
+
+    #[test]
+    fn test_stamp_and_load() {
+        let table = table();
+        let mut graph = old_graph();
+        table.stamp(&mut graph);
+        assert_eq!(graph.library_versions["sample"], 2);
+
+        let path =
+            std::env::temp_dir().join(format!("handjam_migrate_{}.json", std::process::id()));
+        old_graph().save(&path).unwrap();
+        let (graph, reports) = load_migrated(&path, &[table]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reports[0].rewrites.len(), 4);
+        assert_eq!(graph.nodes.len(), 4);
+    }
+
+    #[test]
+    fn test_failed_rule() {
+        // The node v2 would add to feed `add.b` is already there
+        let mut graph = old_graph();
+        let taken = format!("{}/b", graph.nodes[2].id);
+        graph.nodes.push(Node {
+            id: taken.clone(),
+            kind: "one".to_string(),
+            incoming_connections: vec![],
+            data: None,
+        });
+        let before = graph.clone();
+
+        let err = table().apply(&mut graph).unwrap_err();
+        assert_eq!(err.to_string(), format!("Node {} already exists", taken));
+        assert_eq!(graph, before);
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let mut table = table();
+        table.migrations.swap(0, 1);
+        let err = table.apply(&mut old_graph()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Migrations for sample are out of order at version 1"
+        );
+    }
+}
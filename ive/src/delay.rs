@@ -0,0 +1,79 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::str::FromStr;
+
+use crate::dyn_call::{DynCall, DynCallResult, DynPort, InputGetter, NodeValue, OutputSetter};
+
+/// Kind of the built-in delay node.
+pub const DELAY_KIND: &str = "delay";
+/// Name of the delay node's input port.
+pub const DELAY_INPUT: &str = "input";
+
+/// Outputs the value its input had at the end of the previous run, starting
+/// with an initial value.
+///
+/// The input is a feedback input: the executor reads it after every other
+/// node has run, so it can come from later in the graph and close a loop.
+/// Every run then advances the loop by one step.
+pub struct Delay<T: NodeValue> {
+    previous: RefCell<Option<T>>,
+}
+
+impl<T: NodeValue> Delay<T> {
+    pub fn new(initial: Option<T>) -> Self {
+        Self {
+            previous: RefCell::new(initial),
+        }
+    }
+
+    /// Parses the initial value from node data, starting empty without it.
+    pub fn parse(data: Option<&str>) -> Result<Self, T::Err>
+    where
+        T: FromStr,
+    {
+        Ok(Self::new(data.map(|d| d.trim().parse()).transpose()?))
+    }
+}
+
+impl<T: NodeValue> DynCall for Delay<T> {
+    fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        match self.previous.borrow().as_ref() {
+            Some(value) => outputs.some(0, value.clone()),
+            None => outputs.none(0),
+        }
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        DELAY_KIND
+    }
+    fn input_len(&self) -> usize {
+        0
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        T::TYPE
+    }
+    fn feedback_inputs(&self) -> Vec<DynPort> {
+        vec![DynPort {
+            name: DELAY_INPUT,
+            kind: T::TYPE.iter().map(|t| t.to_string()).collect(),
+            type_id: Some(TypeId::of::<T>()),
+            optional: false,
+            variadic: false,
+            owned: false,
+        }]
+    }
+    fn latch(&self, inputs: &InputGetter) -> DynCallResult {
+        let value = match inputs.is_some(0) {
+            true => Some(inputs.fetch::<T>(0)?.clone()),
+            false => None,
+        };
+        *self.previous.borrow_mut() = value;
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::Write;
+
+use crate::dyn_call::{DirtyEnum, DynLinearExec};
+
+/// Values longer than this are cut short so nodes stay readable.
+const MAX_VALUE_LEN: usize = 40;
+
+/// Escapes a string for use inside a quoted DOT attribute.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes a string for use as a field in a `shape=record` label, where
+/// braces, bars and angle brackets have meaning.
+pub fn escape_record(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '{' | '}' | '|' | '<' | '>' | '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn dirty_color(state: DirtyEnum) -> &'static str {
+    match state {
+        DirtyEnum::NeedCompute => "gold",
+        DirtyEnum::Stale => "lightgray",
+        DirtyEnum::Clean => "palegreen",
+    }
+}
+
+fn shorten(s: String) -> String {
+    if s.chars().count() <= MAX_VALUE_LEN {
+        s
+    } else {
+        s.chars()
+            .take(MAX_VALUE_LEN)
+            .chain(std::iter::once('…'))
+            .collect()
+    }
+}
+
+/// Renders an executor as a DOT digraph.  Nodes are colored by their dirty
+/// state and show the current value of each output, and edges are labeled
+/// with the input port they feed.  Feedback edges are dashed.
+pub fn exec_to_dot(exec: &DynLinearExec) -> String {
+    let mut out = String::new();
+    _ = writeln!(out, "digraph {{");
+    _ = writeln!(out, "    node [shape=box, style=filled];");
+    for index in 0..exec.len() {
+        let call = exec.node_call(index);
+        let mut label = format!("{}: {}", index, call.kind());
+        for (port, slot) in call.outputs().iter().zip(exec.output_slots(index)) {
+            let value = if exec.is_none(slot) {
+                "None".to_string()
+            } else {
+                exec.format_value(slot)
+                    .map(shorten)
+                    .unwrap_or_else(|| "?".to_string())
+            };
+            _ = write!(label, "\n{} = {}", port.name, value);
+        }
+        _ = writeln!(
+            out,
+            "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+            index,
+            escape(&label),
+            dirty_color(exec.run_state(index))
+        );
+    }
+    for index in 0..exec.len() {
+        let ports = exec.node_call(index).inputs();
+        for (i, slot) in exec.node_inputs(index).iter().enumerate() {
+            if let Some(from) = exec.slot_owner(*slot) {
+                let port = ports
+                    .get(exec.input_port(index, i))
+                    .map(|p| p.name)
+                    .unwrap_or_default();
+                _ = writeln!(
+                    out,
+                    "    n{} -> n{} [label=\"{}\"];",
+                    from,
+                    index,
+                    escape(port)
+                );
+            }
+        }
+        let ports = exec.node_call(index).feedback_inputs();
+        for (i, slot) in exec.node_feedback(index).iter().enumerate() {
+            if let Some(from) = exec.slot_owner(*slot) {
+                let port = ports.get(i).map(|p| p.name).unwrap_or_default();
+                _ = writeln!(
+                    out,
+                    "    n{} -> n{} [label=\"{}\", style=dashed];",
+                    from,
+                    index,
+                    escape(port)
+                );
+            }
+        }
+    }
+    _ = writeln!(out, "}}");
+    out
+}
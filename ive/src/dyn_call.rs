@@ -1,3 +1,10 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::mem::MaybeUninit;
+
+use crate::observer::ExecObserver;
+use crate::profile::{RunRecorder, RunReport};
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum DirtyEnum {
     NeedCompute,
@@ -7,15 +14,49 @@ pub enum DirtyEnum {
 
 //pub type BoxedAny = Box<dyn std::any::Any>;
 pub struct BoxedAny {
-    any: Box<dyn std::any::Any>,
+    /// The type of the value.  Inline values have nothing else to check
+    /// their type against; boxed values are checked by `downcast_ref`.
+    type_id: TypeId,
+    repr: Repr,
 }
+
+/// Words of inline storage for small `Copy` values
+const INLINE_WORDS: usize = 2;
+
+enum Repr {
+    Boxed(Box<dyn std::any::Any>),
+    /// A `Copy` value that fits in `bytes`, which never needs dropping
+    Inline(MaybeUninit<[u64; INLINE_WORDS]>),
+}
+
 impl BoxedAny {
     pub fn new<T>(value: T) -> BoxedAny
     where
         T: 'static + std::any::Any,
     {
         Self {
-            any: Box::new(value),
+            type_id: TypeId::of::<T>(),
+            repr: Repr::Boxed(Box::new(value)),
+        }
+    }
+
+    /// Stores a small `Copy` value without allocating.  Values too big for
+    /// the inline storage are boxed like `new`.
+    pub fn inline<T>(value: T) -> BoxedAny
+    where
+        T: 'static + Copy,
+    {
+        let fits = std::mem::size_of::<T>() <= std::mem::size_of::<[u64; INLINE_WORDS]>()
+            && std::mem::align_of::<T>() <= std::mem::align_of::<u64>();
+        if !fits {
+            return Self::new(value);
+        }
+        let mut bytes = MaybeUninit::<[u64; INLINE_WORDS]>::uninit();
+        // SAFETY: checked above that T fits in the buffer and its alignment
+        unsafe { bytes.as_mut_ptr().cast::<T>().write(value) };
+        Self {
+            type_id: TypeId::of::<T>(),
+            repr: Repr::Inline(bytes),
         }
     }
 
@@ -23,29 +64,175 @@ impl BoxedAny {
     where
         T: 'static + std::any::Any,
     {
-        self.any
-            .downcast_ref::<T>()
-            .ok_or_else(|| "Unable to downcast any to given type".into())
+        match &self.repr {
+            Repr::Boxed(any) => any
+                .downcast_ref::<T>()
+                .ok_or_else(|| "Unable to downcast any to given type".into()),
+            Repr::Inline(bytes) if self.type_id == TypeId::of::<T>() => {
+                // SAFETY: `type_id` was taken from the T the buffer was
+                // written with in `inline`
+                Ok(unsafe { &*bytes.as_ptr().cast::<T>() })
+            }
+            Repr::Inline(_) => Err("Unable to downcast any to given type".into()),
+        }
+    }
+
+    /// Unwraps the value, moving it out of the box.
+    pub fn into_value<T>(self) -> Result<T, Box<dyn std::error::Error>>
+    where
+        T: 'static + std::any::Any,
+    {
+        match self.repr {
+            Repr::Boxed(any) => any
+                .downcast::<T>()
+                .map(|v| *v)
+                .map_err(|_| "Unable to downcast any to given type".into()),
+            Repr::Inline(bytes) if self.type_id == TypeId::of::<T>() => {
+                // SAFETY: the buffer was written with a T in `inline`, and
+                // only Copy types go inline so reading it out is fine
+                Ok(unsafe { bytes.as_ptr().cast::<T>().read() })
+            }
+            Repr::Inline(_) => Err("Unable to downcast any to given type".into()),
+        }
+    }
+
+    /// Overwrites a boxed value of the same type in place, keeping its
+    /// allocation.  Hands the value back if the types differ.
+    fn replace<T>(&mut self, value: T) -> Result<(), T>
+    where
+        T: 'static + std::any::Any,
+    {
+        match &mut self.repr {
+            Repr::Boxed(any) => match any.downcast_mut::<T>() {
+                Some(slot) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(value),
+            },
+            Repr::Inline(_) => Err(value),
+        }
     }
 }
 
+/// How a node's outputs are written to the store.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StorageMode {
+    /// Every output is boxed afresh each time it's set.
+    #[default]
+    Boxed,
+    /// Outputs overwrite the previous value when it has the same type,
+    /// reusing its allocation, and small `Copy` outputs are kept inline.
+    Arena,
+}
+
 pub type OptionalValue = Option<BoxedAny>;
 pub type AnyInputs<'a> = [&'a BoxedAny];
 pub type AnyOutputs<'a> = [OptionalValue];
 pub type DynCallResult = Result<(), Box<dyn std::error::Error>>;
 pub trait DynCall {
     fn call(&self, inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult;
+    /// Used instead of `call` once the executor has validated the graph, so
+    /// the input and output counts are known to be right and needn't be
+    /// checked again.
+    fn call_unchecked(&self, inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        self.call(inputs, outputs)
+    }
     fn kind(&self) -> &'static str;
     fn input_len(&self) -> usize;
     fn output_len(&self) -> usize;
     fn inputs(&self) -> Vec<DynPort>;
     fn output_type(&self) -> &'static [&'static str];
+    /// The output ports.  A node with a single output names it `value`.
+    fn outputs(&self) -> Vec<DynPort> {
+        match self.output_len() {
+            1 => vec![DynPort {
+                name: OUTPUT_PORT,
+                kind: self.output_type().iter().map(|t| t.to_string()).collect(),
+                type_id: None,
+                optional: false,
+                variadic: false,
+                owned: false,
+            }],
+            _ => vec![],
+        }
+    }
+    /// Formats the value stored in output `index` for display, if the type
+    /// supports it.
+    fn format_output(&self, _index: usize, _value: &BoxedAny) -> Option<String> {
+        None
+    }
+    /// Inputs that are read after the whole graph has run rather than before
+    /// this node runs, so they can be fed from later nodes.
+    fn feedback_inputs(&self) -> Vec<DynPort> {
+        vec![]
+    }
+    /// Called with the feedback inputs at the end of a run in which any of
+    /// them changed.  The node is run again on the next run.
+    fn latch(&self, _inputs: &InputGetter) -> DynCallResult {
+        Ok(())
+    }
 }
 
+/// Name of the output port on single output nodes.
+pub const OUTPUT_PORT: &str = "value";
+
+/// Input index for an input with nothing connected to it, which always
+/// reads as `None`.  Only useful for optional inputs.
+pub const UNCONNECTED: usize = usize::MAX;
+
 pub type DynType = Vec<String>;
 pub struct DynPort {
     pub name: &'static str,
     pub kind: DynType,
+    /// The Rust type of the values on the port, when it's known.  Checked
+    /// instead of `kind`, which can't see through paths or aliases.
+    pub type_id: Option<TypeId>,
+    /// An optional input doesn't need a value for the node to run.
+    pub optional: bool,
+    /// A variadic input takes any number of values.  Only the last input
+    /// can be variadic, and it gets every input index past the others.
+    pub variadic: bool,
+    /// The node takes the input by value.  The executor moves it out of the
+    /// store when nothing else reads it and it was made in the same run, and
+    /// the node clones it otherwise.
+    pub owned: bool,
+}
+
+/// Types the built-in nodes can pass along, which need cloning out of the
+/// store and a type name to report on their ports.
+pub trait NodeValue: Clone + 'static {
+    const TYPE: &'static [&'static str];
+}
+
+macro_rules! node_value {
+    ($($t:ty),*) => {
+        $(impl NodeValue for $t {
+            const TYPE: &'static [&'static str] = &[stringify!($t)];
+        })*
+    };
+}
+node_value!(bool, i8, i16, i32, i64, u8, u16, u32, u64, usize, f32, f64, String);
+
+/// Wrapper used to format a value with `Debug` only when the type implements
+/// it.  Calling `(&DebugProbe(value)).probe_debug()` on a concrete type
+/// picks `ViaDebug` when `T: Debug` and falls back to `ViaNothing` otherwise.
+pub struct DebugProbe<'a, T>(pub &'a T);
+pub trait ViaDebug {
+    fn probe_debug(&self) -> Option<String>;
+}
+impl<T: std::fmt::Debug> ViaDebug for DebugProbe<'_, T> {
+    fn probe_debug(&self) -> Option<String> {
+        Some(format!("{:?}", self.0))
+    }
+}
+pub trait ViaNothing {
+    fn probe_debug(&self) -> Option<String>;
+}
+impl<T> ViaNothing for &DebugProbe<'_, T> {
+    fn probe_debug(&self) -> Option<String> {
+        None
+    }
 }
 
 pub struct DynStorage {
@@ -84,15 +271,74 @@ type InputIndices = Vec<usize>;
 struct ExecNode {
     call: Box<dyn DynCall>,
     input_indices: InputIndices,
+    feedback_indices: InputIndices,
     children: ChildrenIndices,
+    /// Which inputs the node can run without
+    optional: Vec<bool>,
+    /// Whether the last input is variadic
+    variadic: bool,
+    /// Which inputs the node takes by value
+    owned: Vec<bool>,
+    /// Which input indices the executor can move into the node, from the
+    /// last move analysis
+    movable: Vec<bool>,
+    /// The node that makes each input, from the last move analysis
+    sources: Vec<Option<usize>>,
 }
 impl ExecNode {
+    fn new(call: Box<dyn DynCall>, input_indices: InputIndices, children: ChildrenIndices) -> Self {
+        let ports = call.inputs();
+        let optional = ports.iter().map(|p| p.optional).collect();
+        let variadic = ports.last().is_some_and(|p| p.variadic);
+        let owned = ports.iter().map(|p| p.owned).collect();
+        Self {
+            call,
+            input_indices,
+            feedback_indices: Vec::new(),
+            children,
+            optional,
+            variadic,
+            owned,
+            movable: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+    /// The port an input index belongs to
+    fn port_of(&self, input: usize) -> usize {
+        match self.variadic {
+            true => input.min(self.optional.len() - 1),
+            false => input,
+        }
+    }
+    fn is_owned(&self, input: usize) -> bool {
+        self.owned
+            .get(self.port_of(input))
+            .copied()
+            .unwrap_or(false)
+    }
+    fn missing_inputs(&self, inputs: &[OptionalValue]) -> bool {
+        self.input_indices.iter().enumerate().any(|(input, i)| {
+            inputs.get(*i).is_none_or(|v| v.is_none())
+                && !self
+                    .optional
+                    .get(self.port_of(input))
+                    .copied()
+                    .unwrap_or(false)
+        })
+    }
     fn num_inputs(&self) -> usize {
-        assert_eq!(
-            self.call.input_len(),
-            self.input_indices.len(),
-            "Dev Error: Node input length mismatch"
-        );
+        if self.variadic {
+            assert!(
+                self.input_indices.len() + 1 >= self.call.input_len(),
+                "Dev Error: Node input length mismatch"
+            );
+        } else {
+            assert_eq!(
+                self.call.input_len(),
+                self.input_indices.len(),
+                "Dev Error: Node input length mismatch"
+            );
+        }
         self.input_indices.len()
     }
     fn num_outputs(&self) -> usize {
@@ -103,6 +349,15 @@ pub struct DynLinearExec {
     store: DynStorage,
     dirty: DynDirty,
     nodes: Vec<ExecNode>,
+    /// Store slots whose value was moved into their consumer
+    moved: Vec<bool>,
+    /// Whether the wiring changed since the last move analysis
+    moves_stale: bool,
+    mode: StorageMode,
+    /// Whether `validate` passed since the wiring last changed
+    validated: bool,
+    /// Keep the per-node checks even after validation
+    checked: bool,
 }
 
 // Create a DynExecError that is an std::error::Error
@@ -112,6 +367,7 @@ enum DynExecError {
     InputOutOfRange,
     FetchNone,
     ValueIsNone,
+    Invalid(String),
 }
 impl std::fmt::Display for DynExecError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -120,8 +376,8 @@ impl std::fmt::Display for DynExecError {
             DynExecError::InputOutOfRange => write!(f, "Dev Error: Input out of range"),
             DynExecError::FetchNone => write!(f, "Dev Error: Fetch None"),
             DynExecError::ValueIsNone => write!(f, "Dev Error: Value is None"),
+            DynExecError::Invalid(ref reason) => write!(f, "Invalid graph: {}", reason),
         }
-        
     }
 }
 impl std::error::Error for DynExecError {}
@@ -135,6 +391,7 @@ impl std::error::Error for DynExecError {}
 //     }
 // }
 
+#[allow(dead_code)]
 trait InputFetch {
     fn fetch<T>(&self, index: usize) -> &T
     where
@@ -144,22 +401,55 @@ trait InputFetch {
 pub struct InputGetter<'a> {
     values: &'a [OptionalValue],
     indices: &'a [usize],
+    /// Values the executor moved out of the store for this call, by input
+    moved: RefCell<Vec<OptionalValue>>,
 }
 impl<'a> InputGetter<'a> {
     pub fn new(values: &'a [OptionalValue], indices: &'a [usize]) -> Self {
-        Self { values, indices }
+        Self {
+            values,
+            indices,
+            moved: RefCell::new(Vec::new()),
+        }
     }
     pub fn fetch<T>(&'a self, index: usize) -> Result<&'a T, Box<dyn std::error::Error>>
     where
         T: 'static + std::any::Any,
     {
-        let value = &self.values[self.indices[index]];
-        let value = value.as_ref();
+        let value = self
+            .values
+            .get(self.indices[index])
+            .and_then(|v| v.as_ref());
         let value = value.ok_or(DynExecError::FetchNone)?;
         //.unwrap()
         let value = value.value::<T>();
         value
     }
+    /// Takes an input by value.  A value the executor moved out of the
+    /// store for this call is handed over, anything else is cloned.
+    pub fn take<T>(&self, index: usize) -> Result<T, Box<dyn std::error::Error>>
+    where
+        T: 'static + std::any::Any + Clone,
+    {
+        let moved = self
+            .moved
+            .borrow_mut()
+            .get_mut(index)
+            .and_then(Option::take);
+        match moved {
+            Some(value) => value.into_value::<T>(),
+            None => self.fetch::<T>(index).cloned(),
+        }
+    }
+    /// Whether the input has a value to fetch.
+    pub fn is_some(&self, index: usize) -> bool {
+        let moved = self.moved.borrow().get(index).is_some_and(|v| v.is_some());
+        moved
+            || self
+                .values
+                .get(self.indices[index])
+                .is_some_and(|v| v.is_some())
+    }
     pub fn len(&self) -> usize {
         self.indices.len()
     }
@@ -170,6 +460,7 @@ impl<'a> InputGetter<'a> {
 pub struct OutputSetter<'a> {
     values: &'a mut [OptionalValue],
     set_count: usize,
+    mode: StorageMode,
 }
 // impl Drop for OutputSetter<'_> {
 //     fn drop(&mut self) {
@@ -185,15 +476,44 @@ impl<'a> OutputSetter<'a> {
         Self {
             values,
             set_count: 0,
+            mode: StorageMode::Boxed,
+        }
+    }
+    /// Writes outputs the way `mode` says.
+    pub fn with_mode(values: &'a mut [OptionalValue], mode: StorageMode) -> Self {
+        Self {
+            values,
+            set_count: 0,
+            mode,
         }
     }
     pub fn some<T>(&mut self, index: usize, value: T)
     where
         T: 'static + std::any::Any,
     {
-        self.values[index] = Some(BoxedAny::new(value));
+        let value = match (self.mode, &mut self.values[index]) {
+            (StorageMode::Arena, Some(slot)) => slot.replace(value).err(),
+            _ => Some(value),
+        };
+        if let Some(value) = value {
+            self.values[index] = Some(BoxedAny::new(value));
+        }
         self.set_count += 1;
     }
+    /// Like `some`, for `Copy` values, which are stored inline when they're
+    /// small enough.
+    pub fn copy<T>(&mut self, index: usize, value: T)
+    where
+        T: 'static + Copy,
+    {
+        match self.mode {
+            StorageMode::Arena => {
+                self.values[index] = Some(BoxedAny::inline(value));
+                self.set_count += 1;
+            }
+            StorageMode::Boxed => self.some(index, value),
+        }
+    }
     pub fn none(&mut self, index: usize) {
         self.values[index] = None;
         self.set_count += 1;
@@ -221,11 +541,7 @@ pub trait HasChildrenIndices {
 impl DynLinearExec {
     pub fn new(nodes: impl Iterator<Item = Box<dyn DynCall>>) -> Self {
         let nodes = nodes
-            .map(|n| ExecNode {
-                call: n,
-                input_indices: Vec::new(),
-                children: Vec::new(),
-            })
+            .map(|n| ExecNode::new(n, Vec::new(), Vec::new()))
             .collect::<Vec<_>>();
 
         let size = nodes.len();
@@ -234,6 +550,11 @@ impl DynLinearExec {
             store: DynStorage::new(storesize),
             dirty: DynDirty::new(size),
             nodes,
+            moved: vec![false; storesize],
+            moves_stale: true,
+            mode: StorageMode::Boxed,
+            validated: false,
+            checked: cfg!(debug_assertions),
         }
     }
     pub fn build_execution_chain<DESC, DESCITEM>(desc: DESC) -> DynLinearExec
@@ -242,10 +563,12 @@ impl DynLinearExec {
         DESCITEM: HasDynCall + HasInputIndices + HasChildrenIndices,
     {
         let nodes = desc
-            .map(|n| ExecNode {
-                call: n.dyn_call(),
-                input_indices: n.input_indices().collect(),
-                children: n.children_indices().collect(),
+            .map(|n| {
+                ExecNode::new(
+                    n.dyn_call(),
+                    n.input_indices().collect(),
+                    n.children_indices().collect(),
+                )
             })
             .collect::<Vec<_>>();
         let storelen = nodes.iter().map(|n| n.num_outputs()).sum();
@@ -253,6 +576,11 @@ impl DynLinearExec {
             store: DynStorage::new(storelen),
             dirty: DynDirty::new(nodes.len()),
             nodes,
+            moved: vec![false; storelen],
+            moves_stale: true,
+            mode: StorageMode::Boxed,
+            validated: false,
+            checked: cfg!(debug_assertions),
         }
     }
     pub fn new_linear_chain(nodes: impl Iterator<Item = Box<dyn DynCall>>) -> Self {
@@ -282,18 +610,383 @@ impl DynLinearExec {
         v.as_ref().ok_or(DynExecError::ValueIsNone)?.value::<T>()
     }
 
+    /// The number of nodes in the executor.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+    pub fn node_call(&self, index: usize) -> &dyn DynCall {
+        self.nodes[index].call.as_ref()
+    }
+    /// The store slots a node reads its inputs from.
+    pub fn node_inputs(&self, index: usize) -> &[usize] {
+        &self.nodes[index].input_indices
+    }
+    /// The store slots a node latches at the end of a run.
+    pub fn node_feedback(&self, index: usize) -> &[usize] {
+        &self.nodes[index].feedback_indices
+    }
+    /// Which of a node's input ports one of its input indices feeds.  All
+    /// the indices past the fixed ports go to a variadic last port.
+    pub fn input_port(&self, index: usize, input: usize) -> usize {
+        self.nodes[index].port_of(input)
+    }
+    pub fn node_children(&self, index: usize) -> &[usize] {
+        &self.nodes[index].children
+    }
+    /// The store slots a node writes its outputs to.
+    pub fn output_slots(&self, index: usize) -> std::ops::Range<usize> {
+        let start = self.nodes[..index].iter().map(|n| n.num_outputs()).sum();
+        start..start + self.nodes[index].num_outputs()
+    }
+    /// The node that writes to a given store slot.
+    pub fn slot_owner(&self, slot: usize) -> Option<usize> {
+        let mut start = 0;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let end = start + node.num_outputs();
+            if (start..end).contains(&slot) {
+                return Some(i);
+            }
+            start = end;
+        }
+        None
+    }
+    /// Formats the current value in a store slot with the owning node's
+    /// `format_output`.  `None` if the slot is empty or can't be formatted.
+    pub fn format_value(&self, slot: usize) -> Option<String> {
+        let owner = self.slot_owner(slot)?;
+        let value = self.store.values[slot].as_ref()?;
+        let index = slot - self.output_slots(owner).start;
+        self.nodes[owner].call.format_output(index, value)
+    }
+
+    pub fn storage_mode(&self) -> StorageMode {
+        self.mode
+    }
+    /// Changes how outputs are written from the next run on.
+    pub fn set_storage_mode(&mut self, mode: StorageMode) {
+        self.mode = mode;
+    }
+
+    /// Checks every node's wiring and the types on each connection.  Once
+    /// this passes, runs skip the per-node checks and use `call_unchecked`,
+    /// until the wiring changes again.
+    ///
+    /// Ports that know their Rust type are compared by `TypeId`.  Others
+    /// fall back to the type names without their paths, and as a name can
+    /// be an alias, names that still differ don't fail validation.  They
+    /// leave the graph unvalidated instead, so runs keep checking each node.
+    pub fn validate(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let owners = self.slot_owners();
+        let outputs = self
+            .nodes
+            .iter()
+            .map(|n| n.call.outputs())
+            .collect::<Vec<_>>();
+        let starts = (0..self.nodes.len())
+            .map(|i| self.output_slots(i).start)
+            .collect::<Vec<_>>();
+        let store_len = self.store.values.len();
+        let mut unproven = false;
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let kind = node.call.kind();
+            let invalid = |reason: String| -> Box<dyn std::error::Error> {
+                DynExecError::Invalid(format!("node {} ({}) {}", index, kind, reason)).into()
+            };
+
+            let count = node.input_indices.len();
+            let expected = node.call.input_len();
+            let count_ok = match node.variadic {
+                true => count + 1 >= expected,
+                false => count == expected,
+            };
+            if !count_ok {
+                return Err(invalid(format!(
+                    "has {} inputs, expected {}",
+                    count, expected
+                )));
+            }
+
+            let ports = node.call.inputs();
+            let feedback_ports = node.call.feedback_inputs();
+            let connections = node
+                .input_indices
+                .iter()
+                .enumerate()
+                .map(|(input, slot)| (*slot, ports.get(node.port_of(input)), starts[index]))
+                .chain(
+                    node.feedback_indices
+                        .iter()
+                        .enumerate()
+                        .map(|(input, slot)| (*slot, feedback_ports.get(input), store_len)),
+                );
+            for (slot, port, limit) in connections {
+                if slot == UNCONNECTED {
+                    continue;
+                }
+                if slot >= limit {
+                    return Err(invalid(format!("reads slot {} before it's computed", slot)));
+                }
+                let owner = owners[slot];
+                let produced = outputs[owner].get(slot - starts[owner]);
+                let (Some(from), Some(to)) = (produced, port) else {
+                    continue;
+                };
+                match port_types_match(from, to) {
+                    Some(true) => {}
+                    Some(false) => {
+                        return Err(invalid(format!(
+                            "input {} takes {}, but node {} ({}) gives {}",
+                            to.name,
+                            to.kind.join(" "),
+                            owner,
+                            self.nodes[owner].call.kind(),
+                            from.kind.join(" ")
+                        )))
+                    }
+                    None => unproven = true,
+                }
+            }
+        }
+        self.validated = !unproven;
+        Ok(())
+    }
+    pub fn is_validated(&self) -> bool {
+        self.validated
+    }
+    /// Keeps the per-node checks on a validated graph, for debugging.  On by
+    /// default in debug builds.
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
     pub fn run_state(&self, index: usize) -> DirtyEnum {
         self.dirty.state[index]
     }
     pub fn set_runnable(&mut self, index: usize) {
         self.dirty.state[index] = DirtyEnum::NeedCompute;
     }
+    pub fn set_run_state(&mut self, index: usize, state: DirtyEnum) {
+        self.dirty.state[index] = state;
+    }
+
+    /// Moves a node's output values out of the store, leaving `None` behind.
+    pub fn take_values(&mut self, index: usize) -> Vec<OptionalValue> {
+        let slots = self.output_slots(index);
+        self.store.values[slots]
+            .iter_mut()
+            .map(|v| v.take())
+            .collect()
+    }
+    /// Puts output values previously taken with `take_values` back into the
+    /// store for a node with the same number of outputs.
+    pub fn put_values(
+        &mut self,
+        index: usize,
+        values: Vec<OptionalValue>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let slots = self.output_slots(index);
+        if slots.len() != values.len() {
+            return Err(DynExecError::InputOutOfRange.into());
+        }
+        for (slot, value) in slots.zip(values) {
+            self.store.values[slot] = value;
+        }
+        Ok(())
+    }
     pub fn run(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.run_observed(&mut ())
+    }
+
+    /// Runs the graph like `run`, but also records what happened to every
+    /// node and how long each computed node took.
+    pub fn run_instrumented(&mut self) -> Result<RunReport, Box<dyn std::error::Error>> {
+        let mut recorder = RunRecorder::new(self.nodes.len());
+        self.run_observed(&mut recorder)?;
+        Ok(recorder.finish())
+    }
+
+    /// Runs the graph, reporting each node's activity to `observer` as it happens.
+    pub fn run_observed<O>(&mut self, observer: &mut O) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        O: ExecObserver + ?Sized,
+    {
+        self.run_selected(observer, None)
+    }
+
+    /// Brings one node up to date, computing only the nodes it depends on.
+    /// Other nodes that need computing are left for a later run.
+    pub fn evaluate(&mut self, target: usize) -> Result<usize, Box<dyn std::error::Error>> {
+        self.evaluate_observed(&[target], &mut ())
+    }
+
+    /// Like `evaluate`, for several nodes at once, reporting to `observer`.
+    /// Nodes outside the targets' upstream cone are reported as skipped.
+    pub fn evaluate_observed<O>(
+        &mut self,
+        targets: &[usize],
+        observer: &mut O,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        O: ExecObserver + ?Sized,
+    {
+        if targets.iter().any(|t| *t >= self.nodes.len()) {
+            return Err(DynExecError::BadDirtyIndex.into());
+        }
+        let cone = self.upstream_cone(targets);
+        self.run_selected(observer, Some(&cone))
+    }
+
+    /// Marks the given nodes and every node they read from, directly or
+    /// indirectly.  Feedback inputs don't count, they're read after a run.
+    pub fn upstream_cone(&self, targets: &[usize]) -> Vec<bool> {
+        let owners = self.slot_owners();
+        let mut cone = vec![false; self.nodes.len()];
+        let mut stack = targets.to_vec();
+        while let Some(index) = stack.pop() {
+            if cone[index] {
+                continue;
+            }
+            cone[index] = true;
+            let inputs = self.nodes[index].input_indices.iter();
+            stack.extend(inputs.filter_map(|slot| owners.get(*slot)));
+        }
+        cone
+    }
+
+    /// The owning node of every store slot.
+    fn slot_owners(&self) -> Vec<usize> {
+        let mut owners = Vec::with_capacity(self.store.values.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            owners.extend(std::iter::repeat_n(i, node.num_outputs()));
+        }
+        owners
+    }
+
+    /// Which of a node's input indices get their value moved in rather than
+    /// cloned.
+    pub fn movable_inputs(&mut self, index: usize) -> &[bool] {
+        if self.moves_stale {
+            self.analyze_moves();
+        }
+        &self.nodes[index].movable
+    }
+    /// Whether a slot's value was moved into its consumer.  The slot reads
+    /// as `None` until its node runs again.
+    pub fn is_moved(&self, slot: usize) -> bool {
+        self.moved[slot]
+    }
+
+    /// Works out which inputs can be moved into their node.  The node has to
+    /// take the input by value and be the only reader of the slot.
+    ///
+    /// A movable input is still only moved when the node that makes it was
+    /// computed in the same run.  Otherwise its value is the cached one, and
+    /// is cloned so it stays cached.
+    fn analyze_moves(&mut self) {
+        let owners = self.slot_owners();
+        let mut readers = vec![0usize; owners.len()];
+        for node in self.nodes.iter() {
+            for slot in node.input_indices.iter().chain(&node.feedback_indices) {
+                if let Some(r) = readers.get_mut(*slot) {
+                    *r += 1;
+                }
+            }
+        }
+        for node in self.nodes.iter_mut() {
+            node.sources = node
+                .input_indices
+                .iter()
+                .map(|s| owners.get(*s).copied())
+                .collect();
+            let movable = node
+                .input_indices
+                .iter()
+                .enumerate()
+                .map(|(input, slot)| node.is_owned(input) && readers.get(*slot) == Some(&1))
+                .collect();
+            node.movable = movable;
+        }
+        self.moves_stale = false;
+    }
+
+    /// A node about to run that had an input moved out needs the node that
+    /// made it to run again first.
+    fn recompute_moved(&mut self, selected: Option<&[bool]>) {
+        if !self.moved.contains(&true) {
+            return;
+        }
+        let owners = self.slot_owners();
+        // Remaking a value can make more nodes run, so go until nothing
+        // else needs remaking
+        loop {
+            let mut marked = self
+                .dirty
+                .state
+                .iter()
+                .map(|s| *s == DirtyEnum::NeedCompute)
+                .collect::<Vec<_>>();
+            let mut changed = false;
+            for index in 0..self.nodes.len() {
+                if !marked[index] || selected.is_some_and(|s| !s[index]) {
+                    continue;
+                }
+                let node = &self.nodes[index];
+                // Children come after their parents, so they're still ahead
+                for child in node.children.iter() {
+                    marked[*child] = true;
+                }
+                for slot in node.input_indices.iter() {
+                    if self.moved.get(*slot) == Some(&true) {
+                        let owner = &mut self.dirty.state[owners[*slot]];
+                        changed |= *owner != DirtyEnum::NeedCompute;
+                        *owner = DirtyEnum::NeedCompute;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn run_selected<O>(
+        &mut self,
+        observer: &mut O,
+        selected: Option<&[bool]>,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        O: ExecObserver + ?Sized,
+    {
+        if self.moves_stale {
+            self.analyze_moves();
+        }
+        self.recompute_moved(selected);
+
         let nodes = &self.nodes;
         let dirty = &mut self.dirty;
         let store = &mut self.store;
+        let moved = &mut self.moved;
+        let mode = self.mode;
+        // Validated graphs don't need checking on every node
+        let unchecked = self.validated && !self.checked;
 
         let mut compute_count = 0usize;
+        // Which nodes were computed this run, whose outputs can be moved on
+        let mut computed = vec![false; nodes.len()];
+
+        // Which store slots were written this run, for latching feedback
+        let has_feedback = nodes.iter().any(|n| !n.feedback_indices.is_empty());
+        let mut written = match has_feedback {
+            true => vec![false; store.values.len()],
+            false => vec![],
+        };
 
         // The nodes store their outputs in order.  This keeps track of the index of the next output
         let mut output_index = 0;
@@ -304,8 +997,9 @@ impl DynLinearExec {
                 .ok_or(DynExecError::BadDirtyIndex)?;
 
             // As much as I lothe nested indentation, I want to keep the same format as the "algorithm"
-            if *runstate == DirtyEnum::NeedCompute {
-                {
+            let is_selected = selected.is_none_or(|s| s[run_index]);
+            if *runstate == DirtyEnum::NeedCompute && is_selected {
+                if !unchecked {
                     let num_in = node.num_inputs();
                     let input_indicides = node.input_indices.len();
                     assert_eq!(num_in, input_indicides, "Input indices not set correctly");
@@ -318,53 +1012,127 @@ impl DynLinearExec {
                 // The output_index is where that break happens
                 let (inputs, outputs) = {
                     let (i, o) = store.values.split_at_mut(output_index);
-                    // Downgrade our inputs to readonly
+                    // The inputs stay writable so values can be moved out.
                     // We only need our specific output range.
-                    (&i[0..i.len()], &mut o[0..node.num_outputs()])
+                    (i, &mut o[0..node.num_outputs()])
                 };
 
                 // Do a quick sanity check that all the input indicies requested
                 // are in range
-                {
+                if !unchecked {
                     let max_input_index = inputs.len();
-                    if node.input_indices.iter().any(|i| *i >= max_input_index) {
+                    let out_of_range = |i: &usize| *i >= max_input_index && *i != UNCONNECTED;
+                    if node.input_indices.iter().any(out_of_range) {
                         return Err(DynExecError::InputOutOfRange.into());
                     }
                 }
 
-                // See if any of the inputs are None
-                let missing_inputs = node.input_indices.iter().any(|i| inputs[*i].is_none());
+                // See if any of the inputs the node needs are None
+                let missing_inputs = node.missing_inputs(inputs);
 
                 if !missing_inputs {
                     *runstate = DirtyEnum::Clean;
 
+                    // Hand over the inputs nothing else needs, when they
+                    // were only just made
+                    let fresh = |input: usize| node.sources[input].is_some_and(|p| computed[p]);
+                    let taken = match node.movable.contains(&true) {
+                        true => node
+                            .input_indices
+                            .iter()
+                            .zip(node.movable.iter())
+                            .enumerate()
+                            .map(|(input, (slot, movable))| match *movable && fresh(input) {
+                                true => {
+                                    moved[*slot] = true;
+                                    inputs[*slot].take()
+                                }
+                                false => None,
+                            })
+                            .collect(),
+                        false => Vec::new(),
+                    };
+                    let took = taken.iter().map(Option::is_some).collect::<Vec<_>>();
+
                     let fetch = InputGetter {
                         values: inputs,
                         indices: &node.input_indices,
+                        moved: RefCell::new(taken),
                     };
 
-                    let mut setter = OutputSetter {
-                        values: outputs,
-                        set_count: 0,
-                    };
-
-                  //  let current_kind = node.call.kind();
+                    let mut setter = OutputSetter::with_mode(outputs, mode);
 
-                    node.call.call(&fetch, &mut setter)?;
+                    observer.before_node(run_index, node.call.as_ref());
+                    let result = match unchecked {
+                        true => node.call.call_unchecked(&fetch, &mut setter),
+                        false => node.call.call(&fetch, &mut setter),
+                    };
+                    observer.after_node(run_index, node.call.as_ref(), &result);
+                    let mut left = fetch.moved.into_inner();
+                    if result.is_err() {
+                        // Put back what the node didn't get to, and have the
+                        // rest made again next run
+                        for (input, slot) in node.input_indices.iter().enumerate() {
+                            if !took[input] {
+                                continue;
+                            }
+                            match left[input].take() {
+                                Some(value) => {
+                                    inputs[*slot] = Some(value);
+                                    moved[*slot] = false;
+                                }
+                                None => {
+                                    if let Some(source) = node.sources[input] {
+                                        dirty.state[source] = DirtyEnum::NeedCompute;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    result?;
 
+                    computed[run_index] = true;
                     compute_count += 1;
                 } else {
                     dirty.state[run_index] = DirtyEnum::Stale;
                     // This slick one liner sets all the outputs to None
                     outputs.iter_mut().for_each(|o| *o = None);
+                    observer.on_stale(run_index, node.call.as_ref());
+                }
+                let slots = output_index..output_index + node.num_outputs();
+                moved[slots.clone()].fill(false);
+                if has_feedback {
+                    written[slots].fill(true);
                 }
                 // Run our children
                 for child in node.children.iter() {
                     dirty.state[*child] = DirtyEnum::NeedCompute;
                 }
+            } else {
+                observer.on_skipped(run_index, node.call.as_ref());
             }
             output_index += node.num_outputs();
         }
+
+        // Now everything has run, nodes with feedback inputs take their
+        // values for the next run
+        for (run_index, node) in nodes.iter().enumerate() {
+            if node.feedback_indices.is_empty() {
+                continue;
+            }
+            if node
+                .feedback_indices
+                .iter()
+                .any(|i| *i >= store.values.len())
+            {
+                return Err(DynExecError::InputOutOfRange.into());
+            }
+            if node.feedback_indices.iter().any(|i| written[*i]) {
+                let fetch = InputGetter::new(&store.values, &node.feedback_indices);
+                node.call.latch(&fetch)?;
+                dirty.state[run_index] = DirtyEnum::NeedCompute;
+            }
+        }
         Ok(compute_count)
     }
 
@@ -374,7 +1142,54 @@ impl DynLinearExec {
 
     pub fn inputs(&mut self, node_index: usize, indices: Vec<usize>) {
         self.nodes[node_index].input_indices = indices;
+        self.moves_stale = true;
+        self.validated = false;
+    }
+
+    /// Sets the slots for a node's feedback inputs.  Unlike regular inputs
+    /// they can be anywhere in the store.
+    pub fn feedback(&mut self, node_index: usize, indices: Vec<usize>) {
+        self.nodes[node_index].feedback_indices = indices;
+        self.moves_stale = true;
+        self.validated = false;
+    }
+}
+
+/// Whether output `from` can feed input `to`: `Some(true)` if it can,
+/// `Some(false)` if it certainly can't and `None` if only the type names
+/// are known and they differ.  A port with no type matches anything.
+fn port_types_match(from: &DynPort, to: &DynPort) -> Option<bool> {
+    if let (Some(from), Some(to)) = (from.type_id, to.type_id) {
+        return Some(from == to);
+    }
+    if from.kind.is_empty() || to.kind.is_empty() {
+        return Some(true);
+    }
+    match type_name(&from.kind) == type_name(&to.kind) {
+        true => Some(true),
+        false => None,
+    }
+}
+
+/// Type tokens with a leading `&` and every path before a `::` removed, so
+/// `&std::string::String` reads as `String`.
+fn type_name(kind: &[String]) -> Vec<&str> {
+    let mut name = vec![];
+    for token in kind.iter().map(String::as_str).skip_while(|t| *t == "&") {
+        match token {
+            "::" => {
+                // Drop the segment before it, unless the path starts here
+                if name
+                    .last()
+                    .is_some_and(|t| !matches!(*t, "<" | "," | "(" | "[" | "&"))
+                {
+                    name.pop();
+                }
+            }
+            _ => name.push(token),
+        }
     }
+    name
 }
 
 pub fn box_dyn_call<T: DynCall + 'static>(t: T) -> Box<dyn DynCall> {
@@ -0,0 +1,112 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+use crate::dyn_call::{DynCall, DynCallResult, DynPort, InputGetter, NodeValue, OutputSetter};
+
+/// Kind of the built-in switch node.
+pub const SWITCH_KIND: &str = "switch";
+/// Kind of the built-in merge node.
+pub const MERGE_KIND: &str = "merge";
+
+fn port<T: NodeValue>(name: &'static str, optional: bool) -> DynPort {
+    DynPort {
+        name,
+        kind: T::TYPE.iter().map(|t| t.to_string()).collect(),
+        type_id: Some(TypeId::of::<T>()),
+        optional,
+        variadic: false,
+        owned: false,
+    }
+}
+
+/// Sends `value` to its `true` or `false` output depending on `condition`.
+/// The other output is set to `None`, so everything downstream of it goes
+/// stale instead of running.
+pub struct Switch<T: NodeValue> {
+    ty: PhantomData<T>,
+}
+
+impl<T: NodeValue> Switch<T> {
+    pub fn new() -> Self {
+        Self { ty: PhantomData }
+    }
+}
+
+impl<T: NodeValue> Default for Switch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: NodeValue> DynCall for Switch<T> {
+    fn call(&self, inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        let condition = *inputs.fetch::<bool>(0)?;
+        let value = inputs.fetch::<T>(1)?.clone();
+        let (taken, other) = if condition { (0, 1) } else { (1, 0) };
+        outputs.some(taken, value);
+        outputs.none(other);
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        SWITCH_KIND
+    }
+    fn input_len(&self) -> usize {
+        2
+    }
+    fn output_len(&self) -> usize {
+        2
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![port::<bool>("condition", false), port::<T>("value", false)]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        T::TYPE
+    }
+    fn outputs(&self) -> Vec<DynPort> {
+        vec![port::<T>("true", false), port::<T>("false", false)]
+    }
+}
+
+/// Forwards `a` if it has a value, otherwise `b`.  Both inputs are optional,
+/// so this joins the branches of a `Switch` back together.  With neither
+/// present the output is `None`.
+pub struct Merge<T: NodeValue> {
+    ty: PhantomData<T>,
+}
+
+impl<T: NodeValue> Merge<T> {
+    pub fn new() -> Self {
+        Self { ty: PhantomData }
+    }
+}
+
+impl<T: NodeValue> Default for Merge<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: NodeValue> DynCall for Merge<T> {
+    fn call(&self, inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        match (0..inputs.len()).find(|i| inputs.is_some(*i)) {
+            Some(i) => outputs.some(0, inputs.fetch::<T>(i)?.clone()),
+            None => outputs.none(0),
+        }
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        MERGE_KIND
+    }
+    fn input_len(&self) -> usize {
+        2
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![port::<T>("a", true), port::<T>("b", true)]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        T::TYPE
+    }
+}
@@ -1,2 +1,8 @@
+pub mod delay;
+pub mod dot;
 pub mod dyn_call;
-pub mod node_description;
\ No newline at end of file
+pub mod flow;
+pub mod node_description;
+pub mod observer;
+pub mod ports;
+pub mod profile;
\ No newline at end of file
@@ -0,0 +1,50 @@
+use crate::dyn_call::{DynCall, DynCallResult};
+
+/// Receives a callback for every node `DynLinearExec::run_observed` visits.
+/// All methods default to doing nothing, so implementors only pick the
+/// events they care about.  The unit type is the observer used by `run`.
+pub trait ExecObserver {
+    /// Called right before a node's function is called.
+    fn before_node(&mut self, _index: usize, _call: &dyn DynCall) {}
+    /// Called right after a node's function returns, with what it returned.
+    /// An error is still returned from the run after this is called.
+    fn after_node(&mut self, _index: usize, _call: &dyn DynCall, _result: &DynCallResult) {}
+    /// Called when a node needed computing but one of its inputs was missing.
+    fn on_stale(&mut self, _index: usize, _call: &dyn DynCall) {}
+    /// Called when a node wasn't marked as needing compute.
+    fn on_skipped(&mut self, _index: usize, _call: &dyn DynCall) {}
+}
+
+impl ExecObserver for () {}
+
+/// Wraps each node call in a `tracing` span named `node`, and logs stale
+/// nodes and failed calls as events.
+#[cfg(feature = "tracing")]
+#[derive(Default)]
+pub struct TracingObserver {
+    span: Option<tracing::span::EnteredSpan>,
+}
+
+#[cfg(feature = "tracing")]
+impl TracingObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl ExecObserver for TracingObserver {
+    fn before_node(&mut self, index: usize, call: &dyn DynCall) {
+        let span = tracing::debug_span!("node", index, kind = call.kind());
+        self.span = Some(span.entered());
+    }
+    fn after_node(&mut self, index: usize, call: &dyn DynCall, result: &DynCallResult) {
+        if let Err(e) = result {
+            tracing::error!(index, kind = call.kind(), error = %e, "node failed");
+        }
+        self.span = None;
+    }
+    fn on_stale(&mut self, index: usize, call: &dyn DynCall) {
+        tracing::debug!(index, kind = call.kind(), "node stale, missing inputs");
+    }
+}
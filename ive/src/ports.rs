@@ -0,0 +1,82 @@
+//! Typed handles on the ports of `make_dynamicable` nodes.  For a function
+//! `add(a: i32, b: i32) -> i32` the macro generates `AddDynCall::a()` and
+//! `AddDynCall::b()` returning `InputPort<AddDynCall, i32>`, and
+//! `AddDynCall::value()` returning `OutputPort<AddDynCall, i32>`.  An input
+//! that is itself called `value` gets `value_input()` instead.
+//!
+//! Inputs carry the type the node reads, so `&T`, `Option<T>` and `&[&T]`
+//! arguments all give `T`.  Outputs carry the return type as it is.
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A node type generated by `make_dynamicable`.
+pub trait NodeKind {
+    /// What `DynCall::kind` returns, the function's name
+    const KIND: &'static str;
+}
+
+/// An input port on nodes of type `N` taking a `T`.
+pub struct InputPort<N, T: ?Sized> {
+    name: &'static str,
+    types: PhantomData<fn(&N, &T)>,
+}
+
+impl<N, T: ?Sized> InputPort<N, T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            types: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// An output port on nodes of type `N` giving a `T`.
+pub struct OutputPort<N, T: ?Sized> {
+    name: &'static str,
+    types: PhantomData<fn(&N, &T)>,
+}
+
+impl<N, T: ?Sized> OutputPort<N, T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            types: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+// Derives would require `N` and `T` to implement these too
+
+impl<N, T: ?Sized> Clone for InputPort<N, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<N, T: ?Sized> Copy for InputPort<N, T> {}
+
+impl<N, T: ?Sized> Clone for OutputPort<N, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<N, T: ?Sized> Copy for OutputPort<N, T> {}
+
+impl<N, T: ?Sized> fmt::Debug for InputPort<N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "InputPort({})", self.name)
+    }
+}
+
+impl<N, T: ?Sized> fmt::Debug for OutputPort<N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OutputPort({})", self.name)
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::dyn_call::{DynCall, DynCallResult};
+use crate::observer::ExecObserver;
+
+/// What happened to a single node during one run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NodeOutcome {
+    /// The node needed computing and its function was called.
+    Computed,
+    /// The node needed computing but one of its inputs was missing.
+    Stale,
+    /// The node was not marked as needing compute, so it was left alone.
+    Skipped,
+}
+
+#[derive(Clone, Debug)]
+pub struct NodeRun {
+    pub index: usize,
+    pub kind: &'static str,
+    pub outcome: NodeOutcome,
+    /// Offset from the start of the run to when the node started computing.
+    pub start: Duration,
+    /// Wall-clock time spent in the node's call.  Zero unless computed.
+    pub duration: Duration,
+}
+
+/// The result of `DynLinearExec::run_instrumented`.
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    pub started: Instant,
+    pub elapsed: Duration,
+    pub nodes: Vec<NodeRun>,
+}
+
+impl RunReport {
+    /// The number of nodes computed, the same as what `run` returns.
+    pub fn compute_count(&self) -> usize {
+        self.with_outcome(NodeOutcome::Computed).count()
+    }
+    pub fn computed(&self) -> impl Iterator<Item = usize> + '_ {
+        self.with_outcome(NodeOutcome::Computed)
+    }
+    pub fn stale(&self) -> impl Iterator<Item = usize> + '_ {
+        self.with_outcome(NodeOutcome::Stale)
+    }
+    pub fn skipped(&self) -> impl Iterator<Item = usize> + '_ {
+        self.with_outcome(NodeOutcome::Skipped)
+    }
+    fn with_outcome(&self, outcome: NodeOutcome) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .filter(move |n| n.outcome == outcome)
+            .map(|n| n.index)
+    }
+}
+
+pub(crate) struct RunRecorder {
+    started: Instant,
+    node_started: Instant,
+    nodes: Vec<NodeRun>,
+}
+
+impl RunRecorder {
+    pub(crate) fn new(size: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            node_started: now,
+            nodes: Vec::with_capacity(size),
+        }
+    }
+    pub(crate) fn finish(self) -> RunReport {
+        RunReport {
+            started: self.started,
+            elapsed: self.started.elapsed(),
+            nodes: self.nodes,
+        }
+    }
+    fn push(&mut self, index: usize, call: &dyn DynCall, outcome: NodeOutcome, duration: Duration) {
+        self.nodes.push(NodeRun {
+            index,
+            kind: call.kind(),
+            outcome,
+            start: self.node_started.duration_since(self.started),
+            duration,
+        });
+    }
+}
+
+impl ExecObserver for RunRecorder {
+    fn before_node(&mut self, _index: usize, _call: &dyn DynCall) {
+        self.node_started = Instant::now();
+    }
+    fn after_node(&mut self, index: usize, call: &dyn DynCall, _result: &DynCallResult) {
+        let duration = self.node_started.elapsed();
+        self.push(index, call, NodeOutcome::Computed, duration);
+    }
+    fn on_stale(&mut self, index: usize, call: &dyn DynCall) {
+        self.node_started = Instant::now();
+        self.push(index, call, NodeOutcome::Stale, Duration::ZERO);
+    }
+    fn on_skipped(&mut self, index: usize, call: &dyn DynCall) {
+        self.node_started = Instant::now();
+        self.push(index, call, NodeOutcome::Skipped, Duration::ZERO);
+    }
+}
+
+/// Per node totals across every run recorded into an `ExecProfile`.
+#[derive(Clone, Debug, Default)]
+pub struct NodeStats {
+    pub kind: &'static str,
+    pub computed: usize,
+    pub stale: usize,
+    pub skipped: usize,
+    pub total: Duration,
+    pub min: Option<Duration>,
+    pub max: Duration,
+}
+
+impl NodeStats {
+    pub fn mean(&self) -> Duration {
+        if self.computed == 0 {
+            Duration::ZERO
+        } else {
+            // Dividing by a u32 would wrap on very long profiles
+            let nanos = self.total.as_nanos() / self.computed as u128;
+            Duration::new(
+                (nanos / 1_000_000_000) as u64,
+                (nanos % 1_000_000_000) as u32,
+            )
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TraceEvent {
+    index: usize,
+    kind: &'static str,
+    ts: Duration,
+    dur: Duration,
+}
+
+/// How many trace events a profile keeps by default
+pub const DEFAULT_EVENT_LIMIT: usize = 100_000;
+
+/// Aggregates `RunReport`s so expensive nodes stand out over many runs.
+/// The stats cover every run, but only the latest computed nodes are kept
+/// for the trace.
+pub struct ExecProfile {
+    epoch: Instant,
+    runs: usize,
+    nodes: Vec<NodeStats>,
+    events: VecDeque<TraceEvent>,
+    event_limit: usize,
+    dropped_events: usize,
+}
+
+impl Default for ExecProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecProfile {
+    pub fn new() -> Self {
+        Self::with_event_limit(DEFAULT_EVENT_LIMIT)
+    }
+
+    /// A profile whose trace keeps at most `limit` events, dropping the
+    /// oldest first.
+    pub fn with_event_limit(limit: usize) -> Self {
+        Self {
+            epoch: Instant::now(),
+            runs: 0,
+            nodes: Vec::new(),
+            events: VecDeque::new(),
+            event_limit: limit,
+            dropped_events: 0,
+        }
+    }
+
+    pub fn record(&mut self, report: &RunReport) {
+        self.runs += 1;
+        let run_offset = report.started.saturating_duration_since(self.epoch);
+        for node in report.nodes.iter() {
+            if self.nodes.len() <= node.index {
+                self.nodes.resize_with(node.index + 1, NodeStats::default);
+            }
+            let stats = &mut self.nodes[node.index];
+            stats.kind = node.kind;
+            match node.outcome {
+                NodeOutcome::Computed => {
+                    stats.computed += 1;
+                    stats.total += node.duration;
+                    stats.max = stats.max.max(node.duration);
+                    stats.min = Some(stats.min.map_or(node.duration, |m| m.min(node.duration)));
+                    self.push_event(TraceEvent {
+                        index: node.index,
+                        kind: node.kind,
+                        ts: run_offset + node.start,
+                        dur: node.duration,
+                    });
+                }
+                NodeOutcome::Stale => stats.stale += 1,
+                NodeOutcome::Skipped => stats.skipped += 1,
+            }
+        }
+    }
+
+    fn push_event(&mut self, event: TraceEvent) {
+        if self.event_limit == 0 {
+            self.dropped_events += 1;
+            return;
+        }
+        if self.events.len() == self.event_limit {
+            self.events.pop_front();
+            self.dropped_events += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+
+    /// How many trace events were dropped to stay under the limit
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events
+    }
+
+    /// Stats indexed by node index in the executor.
+    pub fn nodes(&self) -> &[NodeStats] {
+        &self.nodes
+    }
+
+    /// A plain text table, most expensive nodes first.
+    pub fn to_table(&self) -> String {
+        let mut order = (0..self.nodes.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| self.nodes[*b].total.cmp(&self.nodes[*a].total));
+
+        let kind_width = self
+            .nodes
+            .iter()
+            .map(|n| n.kind.len())
+            .chain(std::iter::once("kind".len()))
+            .max()
+            .unwrap_or_default();
+
+        let mut out = String::new();
+        _ = writeln!(
+            out,
+            "{:>6}  {:<kind_width$}  {:>8}  {:>6}  {:>8}  {:>12}  {:>12}  {:>12}",
+            "index", "kind", "computed", "stale", "skipped", "total", "mean", "max"
+        );
+        for index in order {
+            let n = &self.nodes[index];
+            _ = writeln!(
+                out,
+                "{:>6}  {:<kind_width$}  {:>8}  {:>6}  {:>8}  {:>12?}  {:>12?}  {:>12?}",
+                index,
+                n.kind,
+                n.computed,
+                n.stale,
+                n.skipped,
+                n.total,
+                n.mean(),
+                n.max
+            );
+        }
+        out
+    }
+
+    /// Every computed node as a complete event in the Chrome trace event
+    /// format, loadable in chrome://tracing or Perfetto.
+    pub fn to_chrome_trace(&self) -> String {
+        let events = self
+            .events
+            .iter()
+            .map(|e| {
+                format!(
+                    r#"{{"name":"{}","cat":"node","ph":"X","ts":{},"dur":{},"pid":1,"tid":1,"args":{{"index":{}}}}}"#,
+                    json_escape(e.kind),
+                    e.ts.as_micros(),
+                    e.dur.as_micros(),
+                    e.index
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(r#"{{"traceEvents":[{}]}}"#, events.join(","))
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => _ = write!(out, "\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,66 @@
+use ive::delay::Delay;
+use ive::dyn_call::{box_dyn_call, DirtyEnum, DynCall, DynLinearExec};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[test]
+fn test_counter_loop() {
+    // delay -> add_one, with add_one fed back into the delay
+    let nodes = vec![
+        box_dyn_call(Delay::<i32>::new(Some(0))),
+        box_dyn_call(AddOneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(1, vec![0]);
+    exec.children(0, vec![1]);
+    exec.feedback(0, vec![1]);
+
+    for step in 1..=3 {
+        assert_eq!(exec.run().unwrap(), 2);
+        assert_eq!(exec.value::<i32>(0).unwrap(), &(step - 1));
+        assert_eq!(exec.value::<i32>(1).unwrap(), &step);
+        assert_eq!(exec.run_state(0), DirtyEnum::NeedCompute);
+    }
+}
+
+#[test]
+fn test_delay_settles() {
+    // The delay comes first but reads from the node after it
+    let nodes = vec![
+        box_dyn_call(Delay::<i32>::parse(Some("5")).unwrap()),
+        box_dyn_call(OneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.feedback(0, vec![1]);
+
+    assert_eq!(exec.run().unwrap(), 2);
+    assert_eq!(exec.value::<i32>(0).unwrap(), &5);
+    assert_eq!(exec.run().unwrap(), 1);
+    assert_eq!(exec.value::<i32>(0).unwrap(), &1);
+    // Nothing feeding it changed, so it has nothing new to output
+    assert_eq!(exec.run().unwrap(), 0);
+}
+
+#[test]
+fn test_delay_ports() {
+    let delay = Delay::<f64>::new(None);
+    assert_eq!(delay.kind(), "delay");
+    assert!(delay.inputs().is_empty());
+    assert_eq!(delay.feedback_inputs()[0].name, "input");
+    assert_eq!(delay.outputs()[0].kind, vec!["f64".to_string()]);
+    assert!(Delay::<i32>::parse(Some("x")).is_err());
+
+    // Without an initial value the first run has nothing to give
+    let mut exec = DynLinearExec::new(vec![box_dyn_call(delay)].into_iter());
+    exec.run().unwrap();
+    assert!(exec.is_none(0));
+}
@@ -0,0 +1,58 @@
+use ive::dot::exec_to_dot;
+use ive::dyn_call::{box_dyn_call, DynLinearExec};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[make_dynamicable()]
+pub fn to_string(a: i32) -> String {
+    format!("\"{}\"", a)
+}
+
+pub struct Opaque;
+
+#[make_dynamicable()]
+pub fn opaque(_a: &String) -> Opaque {
+    Opaque
+}
+
+#[test]
+fn test_format_value() {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(ToStringDynCall {}),
+        box_dyn_call(OpaqueDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    assert_eq!(exec.format_value(0), None);
+
+    exec.run().unwrap();
+    assert_eq!(exec.format_value(0).unwrap(), "1");
+    assert_eq!(exec.format_value(1).unwrap(), r#""\"1\"""#);
+    // No Debug on Opaque
+    assert_eq!(exec.format_value(2), None);
+    assert_eq!(exec.slot_owner(2), Some(2));
+    assert_eq!(exec.slot_owner(3), None);
+}
+
+#[test]
+fn test_exec_to_dot() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(AddOneDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let dot = exec_to_dot(&exec);
+    assert!(dot.contains(r#"n0 [label="0: one\nvalue = None", fillcolor="gold"];"#));
+    assert!(dot.contains(r#"n0 -> n1 [label="a"];"#));
+
+    exec.run().unwrap();
+    let dot = exec_to_dot(&exec);
+    assert!(dot.contains(r#"n1 [label="1: add_one\nvalue = 2", fillcolor="palegreen"];"#));
+}
@@ -0,0 +1,112 @@
+use ive::dyn_call::{box_dyn_call, DirtyEnum, DynCall, DynLinearExec};
+use ive::profile::NodeOutcome;
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn two() -> i32 {
+    2
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[make_dynamicable()]
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+// 0: one -> 1: add_one
+// 2: two -> 3: add_one
+// 4: add(1, 3)
+fn make_exec() -> DynLinearExec {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(TwoDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(AddDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(1, vec![0]);
+    exec.inputs(3, vec![2]);
+    exec.inputs(4, vec![1, 3]);
+    exec.children(0, vec![1]);
+    exec.children(1, vec![4]);
+    exec.children(2, vec![3]);
+    exec.children(3, vec![4]);
+    exec
+}
+
+#[test]
+fn test_evaluate_only_upstream() {
+    let mut exec = make_exec();
+    assert_eq!(
+        exec.upstream_cone(&[1]),
+        vec![true, true, false, false, false]
+    );
+
+    assert_eq!(exec.evaluate(1).unwrap(), 2);
+    assert_eq!(exec.value::<i32>(1).unwrap(), &2);
+    assert!(exec.is_none(3));
+    assert_eq!(exec.run_state(2), DirtyEnum::NeedCompute);
+    assert_eq!(exec.run_state(4), DirtyEnum::NeedCompute);
+
+    // Already up to date
+    assert_eq!(exec.evaluate(1).unwrap(), 0);
+
+    // Picks up the rest, without redoing the first chain
+    assert_eq!(exec.evaluate(4).unwrap(), 3);
+    assert_eq!(exec.value::<i32>(4).unwrap(), &5);
+    assert_eq!(exec.run().unwrap(), 0);
+}
+
+#[test]
+fn test_evaluate_leaves_dirty_nodes() {
+    let mut exec = make_exec();
+    exec.run().unwrap();
+
+    exec.set_runnable(0);
+    exec.set_runnable(2);
+    assert_eq!(exec.evaluate(3).unwrap(), 2);
+    assert_eq!(exec.run_state(0), DirtyEnum::NeedCompute);
+    assert_eq!(exec.run_state(1), DirtyEnum::Clean);
+    assert_eq!(exec.run_state(4), DirtyEnum::NeedCompute);
+
+    let mut recorder = Vec::new();
+    struct Record<'a>(&'a mut Vec<usize>);
+    impl ive::observer::ExecObserver for Record<'_> {
+        fn before_node(&mut self, index: usize, _call: &dyn DynCall) {
+            self.0.push(index);
+        }
+    }
+    exec.evaluate_observed(&[1, 4], &mut Record(&mut recorder))
+        .unwrap();
+    assert_eq!(recorder, vec![0, 1, 4]);
+
+    assert!(exec.evaluate(5).is_err());
+}
+
+#[test]
+fn test_evaluate_report() {
+    let mut exec = make_exec();
+    exec.evaluate(3).unwrap();
+    let report = exec.run_instrumented().unwrap();
+    let outcomes = report.nodes.iter().map(|n| n.outcome).collect::<Vec<_>>();
+    assert_eq!(
+        outcomes,
+        vec![
+            NodeOutcome::Computed,
+            NodeOutcome::Computed,
+            NodeOutcome::Skipped,
+            NodeOutcome::Skipped,
+            NodeOutcome::Computed,
+        ]
+    );
+}
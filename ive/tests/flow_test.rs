@@ -0,0 +1,108 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use ive::dyn_call::{
+    box_dyn_call, DirtyEnum, DynCall, DynCallResult, DynLinearExec, DynPort, InputGetter,
+    OutputSetter,
+};
+use ive::flow::{Merge, Switch};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn two() -> i32 {
+    2
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[make_dynamicable()]
+pub fn double(a: i32) -> i32 {
+    a * 2
+}
+
+// A condition that can be changed between runs
+struct Flag(Rc<Cell<bool>>);
+impl DynCall for Flag {
+    fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        outputs.some(0, self.0.get());
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        "flag"
+    }
+    fn input_len(&self) -> usize {
+        0
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        &["bool"]
+    }
+}
+
+// 0: flag, 1: two, 2: switch(0, 1)
+// 3: add_one(switch.true), 4: double(switch.false)
+// 5: merge(3, 4)
+fn make_exec(flag: &Rc<Cell<bool>>) -> DynLinearExec {
+    let nodes = vec![
+        box_dyn_call(Flag(flag.clone())),
+        box_dyn_call(TwoDynCall {}),
+        box_dyn_call(Switch::<i32>::new()),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(DoubleDynCall {}),
+        box_dyn_call(Merge::<i32>::new()),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(2, vec![0, 1]);
+    exec.inputs(3, vec![2]);
+    exec.inputs(4, vec![3]);
+    exec.inputs(5, vec![4, 5]);
+    exec.children(0, vec![2]);
+    exec.children(1, vec![2]);
+    exec.children(2, vec![3, 4]);
+    exec.children(3, vec![5]);
+    exec.children(4, vec![5]);
+    exec
+}
+
+#[test]
+fn test_switch_branches() {
+    let flag = Rc::new(Cell::new(true));
+    let mut exec = make_exec(&flag);
+
+    // Everything but the false branch
+    assert_eq!(exec.run().unwrap(), 5);
+    assert_eq!(exec.run_state(4), DirtyEnum::Stale);
+    assert!(exec.is_none(3));
+    assert_eq!(exec.value::<i32>(4).unwrap(), &3);
+    assert_eq!(exec.value::<i32>(6).unwrap(), &3);
+
+    flag.set(false);
+    exec.set_runnable(0);
+    assert_eq!(exec.run().unwrap(), 4);
+    assert_eq!(exec.run_state(3), DirtyEnum::Stale);
+    assert_eq!(exec.run_state(4), DirtyEnum::Clean);
+    assert!(exec.is_none(4));
+    assert_eq!(exec.value::<i32>(6).unwrap(), &4);
+}
+
+#[test]
+fn test_merge_ports() {
+    let merge = Merge::<f64>::new();
+    let inputs = merge.inputs();
+    assert!(inputs.iter().all(|p| p.optional));
+    assert_eq!(inputs[0].kind, vec!["f64".to_string()]);
+
+    let switch = Switch::<f64>::new();
+    assert!(switch.inputs().iter().all(|p| !p.optional));
+    let outputs = switch.outputs();
+    assert_eq!(outputs[0].name, "true");
+    assert_eq!(outputs[1].name, "false");
+}
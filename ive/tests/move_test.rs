@@ -0,0 +1,261 @@
+use std::any::TypeId;
+use std::cell::Cell;
+
+use ive::dyn_call::{
+    box_dyn_call, DynCall, DynCallResult, DynLinearExec, DynPort, InputGetter, OutputSetter,
+};
+use ive_macros::make_dynamicable;
+
+thread_local! {
+    static CLONES: Cell<usize> = const { Cell::new(0) };
+}
+
+fn clones() -> usize {
+    CLONES.with(|c| c.get())
+}
+
+pub struct Image {
+    pixels: Vec<u8>,
+}
+
+impl Clone for Image {
+    fn clone(&self) -> Self {
+        CLONES.with(|c| c.set(c.get() + 1));
+        Self {
+            pixels: self.pixels.clone(),
+        }
+    }
+}
+
+#[make_dynamicable()]
+pub fn make_image() -> Image {
+    Image {
+        pixels: vec![1, 2, 3],
+    }
+}
+
+#[make_dynamicable()]
+pub fn brighten(mut img: Image) -> Image {
+    img.pixels.iter_mut().for_each(|p| *p += 10);
+    img
+}
+
+#[make_dynamicable()]
+pub fn darken(mut img: Image, amount: u8) -> Image {
+    img.pixels.iter_mut().for_each(|p| *p -= amount);
+    img
+}
+
+#[make_dynamicable()]
+pub fn width(img: &Image) -> usize {
+    img.pixels.len()
+}
+
+#[make_dynamicable()]
+pub fn one() -> u8 {
+    1
+}
+
+#[test]
+fn test_single_consumer_moves() {
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(BrightenDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    assert!(BrightenDynCall {}.inputs()[0].owned);
+    assert_eq!(exec.movable_inputs(1), &[true]);
+
+    assert_eq!(exec.run().unwrap(), 2);
+    assert_eq!(clones(), 0);
+    assert_eq!(exec.value::<Image>(1).unwrap().pixels, vec![11, 12, 13]);
+    assert!(exec.is_moved(0));
+    assert!(exec.is_none(0));
+
+    // Nothing changed, nothing runs
+    assert_eq!(exec.run().unwrap(), 0);
+
+    // Running the consumer again makes the image again first
+    exec.set_runnable(1);
+    assert_eq!(exec.run().unwrap(), 2);
+    assert_eq!(clones(), 0);
+    assert_eq!(exec.value::<Image>(1).unwrap().pixels, vec![11, 12, 13]);
+}
+
+#[test]
+fn test_cached_value_clones() {
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(BrightenDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    // The image is made in one run and read in the next, so it stays
+    // cached for the consumer to clone
+    assert_eq!(exec.evaluate(0).unwrap(), 1);
+    assert_eq!(exec.run().unwrap(), 1);
+    assert_eq!(clones(), 1);
+    assert!(!exec.is_moved(0));
+    assert_eq!(exec.value::<Image>(0).unwrap().pixels, vec![1, 2, 3]);
+
+    // Running the consumer again doesn't need the image made again
+    exec.set_runnable(1);
+    assert_eq!(exec.run().unwrap(), 1);
+    assert_eq!(clones(), 2);
+    assert_eq!(exec.value::<Image>(1).unwrap().pixels, vec![11, 12, 13]);
+}
+
+#[test]
+fn test_shared_value_clones() {
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(BrightenDynCall {}),
+        box_dyn_call(WidthDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(1, vec![0]);
+    exec.inputs(2, vec![0]);
+    exec.children(0, vec![1, 2]);
+    assert_eq!(exec.movable_inputs(1), &[false]);
+
+    assert_eq!(exec.run().unwrap(), 3);
+    assert_eq!(clones(), 1);
+    assert!(!exec.is_moved(0));
+    assert_eq!(exec.value::<Image>(0).unwrap().pixels, vec![1, 2, 3]);
+    assert_eq!(exec.value::<Image>(1).unwrap().pixels, vec![11, 12, 13]);
+    assert_eq!(exec.value::<usize>(2).unwrap(), &3);
+}
+
+#[test]
+fn test_other_parent_moves() {
+    // Only the image comes from a node that runs with the consumer
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(DarkenDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(2, vec![0, 1]);
+    exec.children(0, vec![2]);
+    exec.children(1, vec![2]);
+    assert_eq!(exec.movable_inputs(2), &[true, false]);
+
+    assert_eq!(exec.run().unwrap(), 3);
+    assert_eq!(clones(), 0);
+    assert!(exec.is_moved(0));
+    assert_eq!(exec.value::<Image>(2).unwrap().pixels, vec![0, 1, 2]);
+
+    // `one` changing runs the consumer, which needs the image made again
+    exec.set_runnable(1);
+    assert_eq!(exec.run().unwrap(), 3);
+    assert_eq!(clones(), 0);
+    assert_eq!(exec.value::<Image>(2).unwrap().pixels, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_other_parent_cached_clones() {
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(DarkenDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(2, vec![0, 1]);
+    exec.children(0, vec![2]);
+    exec.children(1, vec![2]);
+
+    // The image is made before `one` changes, so it's cloned from the cache
+    assert_eq!(exec.evaluate(0).unwrap(), 1);
+    assert_eq!(exec.run().unwrap(), 2);
+    assert_eq!(clones(), 1);
+    assert!(!exec.is_moved(0));
+    assert_eq!(exec.value::<Image>(2).unwrap().pixels, vec![0, 1, 2]);
+}
+
+/// Takes an image by value, failing the first `failures` calls either
+/// before or after taking it.
+struct Consume {
+    fail_after_take: bool,
+    failures: Cell<usize>,
+}
+
+impl Consume {
+    fn fail(&self) -> DynCallResult {
+        match self.failures.get() {
+            0 => Ok(()),
+            n => {
+                self.failures.set(n - 1);
+                Err("failed".into())
+            }
+        }
+    }
+}
+
+impl DynCall for Consume {
+    fn call(&self, inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        if !self.fail_after_take {
+            self.fail()?;
+        }
+        let len = inputs.take::<Image>(0)?.pixels.len();
+        self.fail()?;
+        outputs.some(0, len);
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        "consume"
+    }
+    fn input_len(&self) -> usize {
+        1
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![DynPort {
+            name: "img",
+            kind: vec!["Image".to_string()],
+            type_id: Some(TypeId::of::<Image>()),
+            optional: false,
+            variadic: false,
+            owned: true,
+        }]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        &["usize"]
+    }
+}
+
+fn consume(fail_after_take: bool) -> DynLinearExec {
+    let nodes = vec![
+        box_dyn_call(MakeImageDynCall {}),
+        box_dyn_call(Consume {
+            fail_after_take,
+            failures: Cell::new(1),
+        }),
+    ];
+    DynLinearExec::new_linear_chain(nodes.into_iter())
+}
+
+#[test]
+fn test_failed_call_puts_input_back() {
+    let mut exec = consume(false);
+    assert_eq!(exec.movable_inputs(1), &[true]);
+    assert!(exec.run().is_err());
+    assert!(!exec.is_moved(0));
+    assert_eq!(exec.value::<Image>(0).unwrap().pixels, vec![1, 2, 3]);
+
+    exec.set_runnable(1);
+    assert_eq!(exec.run().unwrap(), 1);
+    assert_eq!(exec.value::<usize>(1).unwrap(), &3);
+}
+
+#[test]
+fn test_failed_call_remakes_taken_input() {
+    let mut exec = consume(true);
+    assert!(exec.run().is_err());
+    assert!(exec.is_none(0));
+
+    // The image is gone, so it gets made again
+    assert_eq!(exec.run().unwrap(), 2);
+    assert_eq!(exec.value::<usize>(1).unwrap(), &3);
+}
@@ -0,0 +1,128 @@
+use ive::dyn_call::{
+    box_dyn_call, DynCall, DynCallResult, DynLinearExec, DynPort, InputGetter, OutputSetter,
+};
+use ive::observer::ExecObserver;
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+// Fails when asked to, otherwise produces no value
+struct NoValueDynCall {
+    fail: bool,
+}
+impl DynCall for NoValueDynCall {
+    fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        if self.fail {
+            return Err("fail".into());
+        }
+        outputs.none(0);
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        "no_value"
+    }
+    fn input_len(&self) -> usize {
+        0
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        &["i32"]
+    }
+}
+
+#[derive(Default)]
+struct EventLog {
+    events: Vec<String>,
+}
+impl ExecObserver for EventLog {
+    fn before_node(&mut self, index: usize, call: &dyn DynCall) {
+        self.events
+            .push(format!("before {} {}", index, call.kind()));
+    }
+    fn after_node(&mut self, index: usize, _call: &dyn DynCall, result: &DynCallResult) {
+        self.events.push(format!(
+            "after {} {}",
+            index,
+            if result.is_ok() { "ok" } else { "err" }
+        ));
+    }
+    fn on_stale(&mut self, index: usize, _call: &dyn DynCall) {
+        self.events.push(format!("stale {}", index));
+    }
+}
+
+#[test]
+fn test_observer_events() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(AddOneDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut log = EventLog::default();
+    let count = exec.run_observed(&mut log).unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(
+        log.events,
+        vec![
+            "before 0 one",
+            "after 0 ok",
+            "before 1 add_one",
+            "after 1 ok"
+        ]
+    );
+
+    // Nothing to do, and on_skipped isn't implemented, so nothing is logged
+    let mut log = EventLog::default();
+    exec.run_observed(&mut log).unwrap();
+    assert!(log.events.is_empty());
+}
+
+#[test]
+fn test_observer_sees_errors() {
+    let nodes = vec![
+        box_dyn_call(NoValueDynCall { fail: true }),
+        box_dyn_call(AddOneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut log = EventLog::default();
+    assert!(exec.run_observed(&mut log).is_err());
+    assert_eq!(log.events, vec!["before 0 no_value", "after 0 err"]);
+}
+
+#[test]
+fn test_observer_stale() {
+    let nodes = vec![
+        box_dyn_call(NoValueDynCall { fail: false }),
+        box_dyn_call(AddOneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut log = EventLog::default();
+    exec.run_observed(&mut log).unwrap();
+    assert_eq!(
+        log.events,
+        vec!["before 0 no_value", "after 0 ok", "stale 1"]
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_observer() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(AddOneDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut observer = ive::observer::TracingObserver::new();
+    assert_eq!(exec.run_observed(&mut observer).unwrap(), 2);
+}
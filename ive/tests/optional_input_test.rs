@@ -0,0 +1,89 @@
+use ive::delay::Delay;
+use ive::dyn_call::{box_dyn_call, DirtyEnum, DynCall, DynLinearExec, UNCONNECTED};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn three() -> i32 {
+    3
+}
+
+#[make_dynamicable()]
+pub fn scale(value: i32, #[default = 2] factor: i32) -> i32 {
+    value * factor
+}
+
+#[make_dynamicable()]
+pub fn offset(value: i32, by: Option<i32>) -> i32 {
+    value + by.unwrap_or(100)
+}
+
+#[make_dynamicable()]
+pub fn greet(name: Option<&String>) -> String {
+    format!("Hello {}", name.map(|n| n.as_str()).unwrap_or("nobody"))
+}
+
+// The store holds a String, so that's what gets borrowed
+#[allow(clippy::ptr_arg)]
+#[make_dynamicable()]
+pub fn shout(#[default = "quiet".to_string()] word: &String) -> String {
+    word.to_uppercase()
+}
+
+#[test]
+fn test_optional_ports() {
+    let inputs = ScaleDynCall {}.inputs();
+    assert!(!inputs[0].optional);
+    assert!(inputs[1].optional);
+    assert_eq!(inputs[1].kind, &["i32"]);
+
+    let inputs = GreetDynCall {}.inputs();
+    assert!(inputs[0].optional);
+    assert_eq!(inputs[0].kind, &["&", "String"]);
+
+    assert!(ShoutDynCall {}.inputs()[0].optional);
+    assert!(OffsetDynCall {}.inputs()[1].optional);
+
+    // The attribute is only for the macro, the function is left alone
+    assert_eq!(scale(3, 4), 12);
+}
+
+#[test]
+fn test_unconnected_inputs() {
+    let nodes = vec![
+        box_dyn_call(ThreeDynCall {}),
+        box_dyn_call(ScaleDynCall {}),
+        box_dyn_call(OffsetDynCall {}),
+        box_dyn_call(GreetDynCall {}),
+        box_dyn_call(ShoutDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(1, vec![0, UNCONNECTED]);
+    exec.inputs(2, vec![0, UNCONNECTED]);
+    exec.inputs(3, vec![UNCONNECTED]);
+    exec.inputs(4, vec![UNCONNECTED]);
+
+    assert_eq!(exec.run().unwrap(), 5);
+    assert_eq!(exec.value::<i32>(1).unwrap(), &6);
+    assert_eq!(exec.value::<i32>(2).unwrap(), &103);
+    assert_eq!(exec.value::<String>(3).unwrap(), "Hello nobody");
+    assert_eq!(exec.value::<String>(4).unwrap(), "QUIET");
+}
+
+#[test]
+fn test_none_inputs() {
+    let nodes = vec![
+        box_dyn_call(ThreeDynCall {}),
+        // Produces no value
+        box_dyn_call(Delay::<i32>::new(None)),
+        box_dyn_call(ScaleDynCall {}),
+        box_dyn_call(ScaleDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    // An optional input without a value falls back to its default
+    exec.inputs(2, vec![0, 1]);
+    // A required one still makes the node stale
+    exec.inputs(3, vec![1, 0]);
+    exec.run().unwrap();
+    assert_eq!(exec.value::<i32>(2).unwrap(), &6);
+    assert_eq!(exec.run_state(3), DirtyEnum::Stale);
+}
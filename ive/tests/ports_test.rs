@@ -0,0 +1,55 @@
+use ive::dyn_call::{DynCall, OUTPUT_PORT};
+use ive::ports::{InputPort, NodeKind, OutputPort};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn scale(value: &f64, #[default = 2.0] by: f64, offset: Option<f64>) -> f64 {
+    value * by + offset.unwrap_or(0.0)
+}
+
+// The store holds Strings, so that's what gets borrowed
+#[allow(clippy::ptr_arg)]
+#[make_dynamicable()]
+pub fn join(separator: &String, parts: &[&String]) -> String {
+    parts
+        .iter()
+        .map(|p| p.as_str())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[allow(clippy::ptr_arg)]
+#[make_dynamicable()]
+pub fn check_not_empty(value: &String) {
+    assert!(!value.is_empty());
+}
+
+#[test]
+fn test_port_handles() {
+    // The handle types are what the nodes read and write
+    let value: InputPort<ScaleDynCall, f64> = ScaleDynCall::value_input();
+    let by: InputPort<ScaleDynCall, f64> = ScaleDynCall::by();
+    let offset: InputPort<ScaleDynCall, f64> = ScaleDynCall::offset();
+    let output: OutputPort<ScaleDynCall, f64> = ScaleDynCall::value();
+    let names = ScaleDynCall {}
+        .inputs()
+        .iter()
+        .map(|p| p.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![value.name(), by.name(), offset.name()]);
+    assert_eq!(output.name(), OUTPUT_PORT);
+
+    let parts: InputPort<JoinDynCall, String> = JoinDynCall::parts();
+    let _: OutputPort<JoinDynCall, String> = JoinDynCall::value();
+    assert_eq!(parts.name(), "parts");
+
+    // No output, so the input keeps its name
+    let value: InputPort<CheckNotEmptyDynCall, String> = CheckNotEmptyDynCall::value();
+    assert_eq!(value.name(), "value");
+}
+
+#[test]
+fn test_node_kind() {
+    assert_eq!(ScaleDynCall::KIND, ScaleDynCall {}.kind());
+    assert_eq!(JoinDynCall::KIND, "join");
+}
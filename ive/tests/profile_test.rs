@@ -0,0 +1,144 @@
+use ive::dyn_call::{
+    box_dyn_call, DynCall, DynCallResult, DynLinearExec, DynPort, InputGetter, OutputSetter,
+};
+use ive::profile::{ExecProfile, NodeOutcome, NodeStats};
+use ive_macros::make_dynamicable;
+use std::time::Duration;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+// A node that never produces a value, so everything downstream goes stale.
+struct NothingDynCall;
+impl DynCall for NothingDynCall {
+    fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        outputs.none(0);
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        "nothing"
+    }
+    fn input_len(&self) -> usize {
+        0
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<DynPort> {
+        vec![]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        &["i32"]
+    }
+}
+
+#[test]
+fn test_run_instrumented() {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let report = exec.run_instrumented().unwrap();
+    assert_eq!(report.compute_count(), 3);
+    assert_eq!(report.computed().collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(report.nodes[1].kind, "add_one");
+    assert_eq!(exec.value::<i32>(2).unwrap(), &3);
+
+    // Nothing is dirty, so every node is skipped
+    let report = exec.run_instrumented().unwrap();
+    assert_eq!(report.compute_count(), 0);
+    assert_eq!(report.skipped().collect::<Vec<_>>(), vec![0, 1, 2]);
+
+    exec.set_runnable(1);
+    let report = exec.run_instrumented().unwrap();
+    assert_eq!(report.computed().collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(report.nodes[0].outcome, NodeOutcome::Skipped);
+}
+
+#[test]
+fn test_run_instrumented_stale() {
+    let nodes = vec![
+        box_dyn_call(NothingDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let report = exec.run_instrumented().unwrap();
+    assert_eq!(report.computed().collect::<Vec<_>>(), vec![0]);
+    assert_eq!(report.stale().collect::<Vec<_>>(), vec![1]);
+}
+
+#[test]
+fn test_profile() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(AddOneDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut profile = ExecProfile::new();
+    for _ in 0..3 {
+        exec.set_runnable(0);
+        profile.record(&exec.run_instrumented().unwrap());
+    }
+    exec.set_runnable(1);
+    profile.record(&exec.run_instrumented().unwrap());
+
+    assert_eq!(profile.runs(), 4);
+    assert_eq!(profile.nodes()[0].kind, "one");
+    assert_eq!(profile.nodes()[0].computed, 3);
+    assert_eq!(profile.nodes()[0].skipped, 1);
+    assert_eq!(profile.nodes()[1].computed, 4);
+
+    let table = profile.to_table();
+    assert!(table.contains("add_one"));
+    assert_eq!(table.lines().count(), 3);
+
+    let trace = profile.to_chrome_trace();
+    assert!(trace.starts_with(r#"{"traceEvents":["#));
+    assert_eq!(trace.matches(r#""ph":"X""#).count(), 7);
+}
+
+#[test]
+fn test_profile_event_limit() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(AddOneDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+
+    let mut profile = ExecProfile::with_event_limit(3);
+    for _ in 0..3 {
+        exec.set_runnable(0);
+        profile.record(&exec.run_instrumented().unwrap());
+    }
+
+    // The stats still cover every run
+    assert_eq!(profile.nodes()[1].computed, 3);
+    assert_eq!(profile.dropped_events(), 3);
+    let trace = profile.to_chrome_trace();
+    assert_eq!(trace.matches(r#""ph":"X""#).count(), 3);
+}
+
+#[test]
+fn test_mean() {
+    let stats = NodeStats {
+        computed: 4,
+        total: Duration::from_millis(10),
+        ..Default::default()
+    };
+    assert_eq!(stats.mean(), Duration::from_micros(2500));
+    assert_eq!(NodeStats::default().mean(), Duration::ZERO);
+
+    // More computes than fit in a u32
+    let stats = NodeStats {
+        computed: 1 << 32,
+        total: Duration::from_secs(1 << 32),
+        ..Default::default()
+    };
+    assert_eq!(stats.mean(), Duration::from_secs(1));
+}
@@ -0,0 +1,91 @@
+use ive::dyn_call::{box_dyn_call, BoxedAny, DynLinearExec, StorageMode};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn zero() -> i32 {
+    0
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[make_dynamicable()]
+pub fn label(a: i32) -> String {
+    format!("value {}", a)
+}
+
+/// Shares a name with a `Copy` type from std, but isn't `Copy`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub names: Vec<String>,
+}
+
+#[make_dynamicable()]
+pub fn options(a: i32) -> Options {
+    Options {
+        names: vec![a.to_string()],
+    }
+}
+
+fn make_exec(mode: StorageMode) -> DynLinearExec {
+    let nodes = vec![
+        box_dyn_call(ZeroDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(LabelDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    exec.set_storage_mode(mode);
+    exec
+}
+
+#[test]
+fn test_arena_matches_boxed() {
+    for mode in [StorageMode::Boxed, StorageMode::Arena] {
+        let mut exec = make_exec(mode);
+        assert_eq!(exec.storage_mode(), mode);
+        for _ in 0..3 {
+            exec.set_runnable(0);
+            assert_eq!(exec.run().unwrap(), 3);
+            assert_eq!(exec.value::<i32>(1).unwrap(), &1);
+            assert_eq!(exec.value::<String>(2).unwrap(), "value 1");
+            assert!(exec.value::<u32>(1).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_arena_reuses_boxes() {
+    let mut exec = make_exec(StorageMode::Arena);
+    exec.run().unwrap();
+    let first = exec.value::<String>(2).unwrap() as *const String;
+
+    exec.set_runnable(0);
+    exec.run().unwrap();
+    let second = exec.value::<String>(2).unwrap() as *const String;
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_inline_values() {
+    let small = BoxedAny::inline(7u8);
+    assert_eq!(small.value::<u8>().unwrap(), &7);
+    assert!(small.value::<i8>().is_err());
+    assert_eq!(small.into_value::<u8>().unwrap(), 7);
+
+    // Too big to go inline, so it's boxed instead
+    let big = BoxedAny::inline([1u64, 2, 3]);
+    assert_eq!(big.value::<[u64; 3]>().unwrap(), &[1, 2, 3]);
+}
+
+#[test]
+fn test_named_like_copy_type() {
+    for mode in [StorageMode::Boxed, StorageMode::Arena] {
+        let nodes = vec![box_dyn_call(ZeroDynCall {}), box_dyn_call(OptionsDynCall {})];
+        let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+        exec.set_storage_mode(mode);
+        exec.run().unwrap();
+        assert_eq!(exec.value::<Options>(1).unwrap().names, vec!["0"]);
+    }
+}
@@ -2,7 +2,7 @@ use std::{any::Any, collections::HashMap};
 
 use ive::dyn_call::{BoxedAny, InputGetter, OutputSetter};
 
-fn extract_option_from<'a, T>(any: &'a dyn Any) -> anyhow::Result<Option<&'a T>>
+fn extract_option_from<T>(any: &dyn Any) -> anyhow::Result<Option<&T>>
 where
     Option<T>: 'static,
 {
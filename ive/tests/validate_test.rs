@@ -0,0 +1,163 @@
+use ive::dyn_call::{
+    box_dyn_call, DynCall, DynCallResult, DynLinearExec, InputGetter, OutputSetter,
+};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn add_one(a: i32) -> i32 {
+    a + 1
+}
+
+#[make_dynamicable()]
+pub fn add(a: &i32, b: &i32) -> i32 {
+    a + b
+}
+
+#[make_dynamicable()]
+pub fn halve(a: f32) -> f32 {
+    a / 2.0
+}
+
+#[allow(clippy::ptr_arg)]
+#[make_dynamicable()]
+pub fn shout(word: &String) -> String {
+    word.to_uppercase()
+}
+
+#[make_dynamicable()]
+pub fn halve_f64(a: f64) -> f64 {
+    a / 2.0
+}
+
+/// A hand written node that only names its output type
+struct Named {
+    ty: &'static [&'static str],
+    value: fn(&mut OutputSetter),
+}
+impl DynCall for Named {
+    fn call(&self, _inputs: &InputGetter, outputs: &mut OutputSetter) -> DynCallResult {
+        (self.value)(outputs);
+        Ok(())
+    }
+    fn kind(&self) -> &'static str {
+        "named"
+    }
+    fn input_len(&self) -> usize {
+        0
+    }
+    fn output_len(&self) -> usize {
+        1
+    }
+    fn inputs(&self) -> Vec<ive::dyn_call::DynPort> {
+        vec![]
+    }
+    fn output_type(&self) -> &'static [&'static str] {
+        self.ty
+    }
+}
+
+fn make_exec() -> DynLinearExec {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(AddOneDynCall {}),
+        box_dyn_call(AddDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(1, vec![0]);
+    exec.inputs(2, vec![0, 1]);
+    exec.children(0, vec![1, 2]);
+    exec.children(1, vec![2]);
+    exec
+}
+
+#[test]
+fn test_validated_run() {
+    for checked in [true, false] {
+        let mut exec = make_exec();
+        exec.set_checked(checked);
+        assert!(!exec.is_validated());
+        exec.validate().unwrap();
+        assert!(exec.is_validated());
+
+        assert_eq!(exec.run().unwrap(), 3);
+        assert_eq!(exec.value::<i32>(2).unwrap(), &3);
+        exec.set_runnable(0);
+        assert_eq!(exec.run().unwrap(), 3);
+        assert_eq!(exec.value::<i32>(2).unwrap(), &3);
+    }
+}
+
+#[test]
+fn test_rewiring_needs_validating_again() {
+    let mut exec = make_exec();
+    exec.validate().unwrap();
+    exec.inputs(2, vec![1, 1]);
+    assert!(!exec.is_validated());
+}
+
+#[test]
+fn test_input_count() {
+    let mut exec = make_exec();
+    exec.inputs(2, vec![0]);
+    let err = exec.validate().unwrap_err().to_string();
+    assert_eq!(err, "Invalid graph: node 2 (add) has 1 inputs, expected 2");
+}
+
+#[test]
+fn test_input_order() {
+    let mut exec = make_exec();
+    exec.inputs(1, vec![2]);
+    let err = exec.validate().unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Invalid graph: node 1 (add_one) reads slot 2 before it's computed"
+    );
+}
+
+#[test]
+fn test_type_mismatch() {
+    let nodes = vec![box_dyn_call(OneDynCall {}), box_dyn_call(HalveDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    let err = exec.validate().unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Invalid graph: node 1 (halve) input a takes f32, but node 0 (one) gives i32"
+    );
+    assert!(!exec.is_validated());
+}
+
+#[test]
+fn test_type_paths() {
+    // The names differ only in the path, so the graph validates
+    let named = Named {
+        ty: &["std", "::", "string", "::", "String"],
+        value: |o| o.some(0, "hi".to_string()),
+    };
+    let nodes = vec![box_dyn_call(named), box_dyn_call(ShoutDynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    exec.validate().unwrap();
+    assert!(exec.is_validated());
+    exec.run().unwrap();
+    assert_eq!(exec.value::<String>(1).unwrap(), "HI");
+}
+
+#[test]
+fn test_type_alias() {
+    // `Meters` could be an alias for f64, so this can't be ruled out, but
+    // the graph isn't validated either
+    let named = Named {
+        ty: &["Meters"],
+        value: |o| o.some(0, 3.0f64),
+    };
+    let nodes = vec![box_dyn_call(named), box_dyn_call(HalveF64DynCall {})];
+    let mut exec = DynLinearExec::new_linear_chain(nodes.into_iter());
+    exec.validate().unwrap();
+    assert!(!exec.is_validated());
+    exec.run().unwrap();
+    assert_eq!(exec.value::<f64>(1).unwrap(), &1.5);
+}
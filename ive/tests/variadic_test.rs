@@ -0,0 +1,73 @@
+use ive::delay::Delay;
+use ive::dyn_call::{box_dyn_call, DirtyEnum, DynCall, DynLinearExec};
+use ive_macros::make_dynamicable;
+
+#[make_dynamicable()]
+pub fn one() -> i32 {
+    1
+}
+
+#[make_dynamicable()]
+pub fn two() -> i32 {
+    2
+}
+
+#[make_dynamicable()]
+pub fn sum(values: &[&i32]) -> i32 {
+    values.iter().copied().sum()
+}
+
+// Reads the values as digits, so the order shows
+#[make_dynamicable()]
+pub fn digits(base: i32, values: &[i32]) -> i32 {
+    values.iter().fold(0, |acc, v| acc * base + v)
+}
+
+#[test]
+fn test_variadic_ports() {
+    let inputs = SumDynCall {}.inputs();
+    assert_eq!(inputs.len(), 1);
+    assert!(inputs[0].variadic);
+    assert_eq!(inputs[0].kind, &["&", "i32"]);
+
+    let inputs = DigitsDynCall {}.inputs();
+    assert!(!inputs[0].variadic);
+    assert!(inputs[1].variadic);
+    assert_eq!(inputs[1].kind, &["i32"]);
+}
+
+#[test]
+fn test_variadic_run() {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(TwoDynCall {}),
+        box_dyn_call(SumDynCall {}),
+        box_dyn_call(DigitsDynCall {}),
+        box_dyn_call(SumDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(2, vec![0, 1, 1]);
+    exec.inputs(3, vec![2, 1, 0, 2]);
+    exec.inputs(4, vec![]);
+    assert_eq!(exec.input_port(3, 0), 0);
+    assert_eq!(exec.input_port(3, 3), 1);
+
+    assert_eq!(exec.run().unwrap(), 5);
+    assert_eq!(exec.value::<i32>(2).unwrap(), &5);
+    // 2, 1 and 5 in base 5
+    assert_eq!(exec.value::<i32>(3).unwrap(), &(2 * 25 + 5 + 5));
+    assert_eq!(exec.value::<i32>(4).unwrap(), &0);
+}
+
+#[test]
+fn test_variadic_missing_value() {
+    let nodes = vec![
+        box_dyn_call(OneDynCall {}),
+        box_dyn_call(Delay::<i32>::new(None)),
+        box_dyn_call(SumDynCall {}),
+    ];
+    let mut exec = DynLinearExec::new(nodes.into_iter());
+    exec.inputs(2, vec![0, 1]);
+    exec.run().unwrap();
+    assert_eq!(exec.run_state(2), DirtyEnum::Stale);
+}
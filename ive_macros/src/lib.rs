@@ -202,7 +202,7 @@ pub fn ive_chain(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         Vec::<PMIdent>::new().into_iter(),
         0,
         &format_ident!("zero"),
-        vec![1].iter(),
+        [1].iter(),
     );
 
     let operations = operations.collect::<Vec<_>>();
@@ -19,15 +19,6 @@ struct PatTypeWrapper<'a> {
     ty: &'a syn::PatType,
 }
 impl<'a> PatTypeWrapper<'a> {
-    fn is_ref(&self) -> bool {
-        match *self.ty.ty {
-            syn::Type::Reference(_) => true,
-            _ => false,
-        }
-    }
-    fn tokens(&self) -> proc_macro2::TokenStream {
-        self.ty.ty.to_token_stream()
-    }
     fn name(&self) -> TokenResult<syn::Ident> {
         let pat = &*self.ty.pat;
         match pat {
@@ -35,30 +26,18 @@ impl<'a> PatTypeWrapper<'a> {
             _ => Err(syn::Error::new(self.ty.pat.span(), "Expected identifier")),
         }
     }
-
-    fn type_strings(&self) -> impl Iterator<Item = String> {
-        self.tokens().into_iter().map(|x| x.to_string())
-    }
 }
 struct FnArgWrapper<'a> {
     arg: &'a syn::FnArg,
 }
 impl<'a> FnArgWrapper<'a> {
-    fn typed(&self) -> TokenResult<PatTypeWrapper> {
+    fn typed(&self) -> TokenResult<PatTypeWrapper<'_>> {
         match self.arg {
             syn::FnArg::Typed(ty) => Ok(PatTypeWrapper { ty }),
             _ => Err(syn::Error::new(self.arg.span(), "Expected typed argument")),
         }
     }
 
-    fn is_ref(&self) -> TokenResult<bool> {
-        Ok(self.typed()?.is_ref())
-    }
-
-    fn tokens(&self) -> TokenResult<proc_macro2::TokenStream> {
-        Ok(self.typed()?.tokens())
-    }
-
     fn name(&self) -> TokenResult<syn::Ident> {
         self.typed()?.name()
     }
@@ -66,6 +45,93 @@ impl<'a> FnArgWrapper<'a> {
     fn span(&self) -> proc_macro2::Span {
         self.arg.span()
     }
+
+    /// The expression from a `#[default = expr]` attribute, if there is one.
+    fn default(&self) -> TokenResult<Option<syn::Expr>> {
+        let attr = self
+            .typed()?
+            .ty
+            .attrs
+            .iter()
+            .find(|a| a.path.is_ident(DEFAULT_ATTR));
+        match attr {
+            Some(attr) => Ok(Some(syn::parse2::<DefaultValue>(attr.tokens.clone())?.0)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the argument is declared as `Option<...>`
+    fn is_option(&self) -> TokenResult<bool> {
+        Ok(option_inner(&self.typed()?.ty.ty).is_some())
+    }
+
+    /// The argument's type with any `Option` around it removed.  This is
+    /// what the port carries.
+    fn port_type(&self) -> TokenResult<&'a syn::Type> {
+        let ty = match self.arg {
+            syn::FnArg::Typed(ty) => &*ty.ty,
+            _ => return Err(syn::Error::new(self.arg.span(), "Expected typed argument")),
+        };
+        Ok(option_inner(ty).unwrap_or(ty))
+    }
+
+    /// The element type of a slice argument, which takes every input past
+    /// the other arguments.
+    fn variadic_elem(&self) -> TokenResult<Option<&'a syn::Type>> {
+        match self.port_type()? {
+            syn::Type::Reference(r) => match &*r.elem {
+                syn::Type::Slice(slice) => Ok(Some(&*slice.elem)),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn is_optional(&self) -> TokenResult<bool> {
+        Ok(self.is_option()? || self.default()?.is_some())
+    }
+}
+
+const DEFAULT_ATTR: &str = "default";
+
+/// The `= expr` part of `#[default = expr]`
+struct DefaultValue(syn::Expr);
+impl syn::parse::Parse for DefaultValue {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![=]>()?;
+        Ok(Self(input.parse()?))
+    }
+}
+
+/// The `T` in `Option<T>`
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.first()? {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Removes the `#[default]` attributes from the arguments, which only mean
+/// something to this macro.
+fn strip_default_attrs(f: &mut ItemFn) {
+    for arg in f.sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(ty) = arg {
+            ty.attrs.retain(|a| !a.path.is_ident(DEFAULT_ATTR));
+        }
+    }
 }
 struct FunctionWrapper<'a> {
     input_fn: &'a ItemFn,
@@ -285,43 +351,134 @@ pub fn make_dynamicable_work(f: ItemFn) -> TokenResult<TokenStream> {
 
     let wrapper = create_dyn_wrapper(&fw)?;
 
+    let mut f = f.clone();
+    strip_default_attrs(&mut f);
     Ok(quote! {
         #f
         #wrapper
     })
 }
 
+/// Whether an argument of type `ty` is taken by value without being
+/// copyable, so the node owns it.
+fn is_owned(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(_) => false,
+        _ => match ty.to_token_stream().into_iter().next() {
+            Some(first) => !COPYABLE_TYPES.contains(&first.to_string().as_str()),
+            None => false,
+        },
+    }
+}
+
+/// Code fetching input `index` as `ty`.  References borrow from the store,
+/// copyable types are copied and anything else is taken by value.
+fn fetch_input(ty: &syn::Type, span: proc_macro2::Span, index: TokenStream) -> TokenResult<TokenStream> {
+    if is_owned(ty) {
+        return Ok(quote! {
+            inputs.take::<#ty>(#index)?
+        });
+    }
+    let mut tokens = ty.to_token_stream().into_iter();
+    let is_ref = matches!(ty, syn::Type::Reference(_));
+    let deref = if !is_ref {
+        if ty.to_token_stream().is_empty() {
+            return Err(syn::Error::new(span, "expected a type"));
+        }
+        quote! { * }
+    } else {
+        // there's a & in the front, strip it off
+        tokens.next();
+        quote! {}
+    };
+    let tokens = tokens.collect::<TokenStream>();
+    Ok(quote! {
+         #deref inputs.fetch::<#tokens>(#index)?
+    })
+}
+
+/// Returns the expression passed for each argument, and the locals that have
+/// to be declared before the call so references to them outlive it.
 fn pull_inputs<'a>(
     inputs: impl Iterator<Item = FnArgWrapper<'a>>,
-) -> TokenResult<Vec<TokenStream>> {
-    let pull = inputs.enumerate().map(|(i, ty)| {
-        let mut tokens = ty.tokens()?.into_iter();
-        let deref = if !ty.is_ref()? {
-            let first = ty.tokens()?.into_iter().next().ok_or_else(|| syn::Error::new(ty.span(), "expected a type"))?.to_string();
-
-            if !COPYABLE_TYPES.iter().any(|v| *v == first.as_str()) {
-                return Err(syn::Error::new(ty.span(), "non-ref argument must be copyable"))
+) -> TokenResult<(Vec<TokenStream>, Vec<TokenStream>)> {
+    let inputs = inputs.collect::<Vec<_>>();
+    let count = inputs.len();
+    let mut locals = vec![];
+    let pull = inputs.into_iter().enumerate().map(|(i, ty)| {
+        let port_type = ty.port_type()?;
+
+        if let Some(elem) = ty.variadic_elem()? {
+            if i + 1 != count {
+                return Err(syn::Error::new(ty.span(), "only the last argument can be a slice"));
             }
-            quote! { * }
-        } else {
-            // there's a & in the front, strip it off
-            tokens.next();
-            quote! {}
-        };
-        let tokens = tokens.collect::<TokenStream>();
-        Ok(quote! {
-             #deref inputs.fetch::<#tokens>(#i)?
-        })
+            if ty.is_optional()? {
+                return Err(syn::Error::new(ty.span(), "a slice argument can't be optional"));
+            }
+            // Every input from here on
+            let fetch = fetch_input(elem, ty.span(), quote! { i })?;
+            let name = format_ident!("variadic_{}", i);
+            locals.push(quote! {
+                let mut #name = Vec::with_capacity(inputs.len() - #i);
+                for i in #i..inputs.len() {
+                    #name.push(#fetch);
+                }
+            });
+            return Ok(quote! { &#name });
+        }
+
+        let is_ref = matches!(port_type, syn::Type::Reference(_));
+        let fetch = fetch_input(port_type, ty.span(), quote! { #i })?;
+
+        match (ty.is_option()?, ty.default()?) {
+            (true, Some(_)) => Err(syn::Error::new(ty.span(), "an Option argument can't also have a default")),
+            (true, None) => Ok(quote! {
+                if inputs.is_some(#i) { Some(#fetch) } else { None }
+            }),
+            (false, Some(default)) => {
+                let name = format_ident!("default_{}", i);
+                locals.push(quote! { let #name; });
+                let value = if is_ref { quote! { &#name } } else { quote! { #name } };
+                Ok(quote! {
+                    if inputs.is_some(#i) { #fetch } else { #name = #default; #value }
+                })
+            }
+            (false, None) => Ok(fetch),
+        }
     });
     let tokens = pull.collect::<TokenResult<Vec<_>>>()?;
-    Ok(tokens)
+    Ok((tokens, locals))
 }
 
-fn store_outputs(_output: &TypeWrapper) -> TokenResult<TokenStream> {
-    //    let output_type = output.ty.to_token_stream().into_iter().map(|t| t.to_string());
-    Ok(quote! {
-        outputs.some(0, output);
-    })
+/// Whether `ty` is a plain primitive like `i32`.  Names are all the macro
+/// has to go on, and a user's type can share one with a `Copy` type from
+/// std, but nothing can shadow a primitive without also breaking its use.
+fn is_primitive(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .is_some_and(|ident| PRIMITIVE_TYPES.contains(&ident.to_string().as_str())),
+        _ => false,
+    }
+}
+
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32",
+    "u64", "u128", "usize",
+];
+
+fn store_outputs(output: &TypeWrapper) -> TokenResult<TokenStream> {
+    // Only primitives are known to be `Copy`, which `copy` needs
+    if is_primitive(output.ty) {
+        Ok(quote! {
+            outputs.copy(0, output);
+        })
+    } else {
+        Ok(quote! {
+            outputs.some(0, output);
+        })
+    }
 }
 
 fn input_len(fw: &FunctionWrapper) -> TokenStream {
@@ -334,7 +491,7 @@ fn input_len(fw: &FunctionWrapper) -> TokenStream {
 }
 
 fn output_len(fw: &FunctionWrapper) -> TokenStream {
-    let len = if let Some(_) = fw.output() { 1usize } else { 0usize };
+    let len = if fw.output().is_some() { 1usize } else { 0usize };
     quote! {
         fn output_len(&self) -> usize {
             #len
@@ -344,17 +501,34 @@ fn output_len(fw: &FunctionWrapper) -> TokenStream {
 
 fn call_dyncall(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
     let fnname = fw.name();
-    let input_pull = pull_inputs(fw.inputs())?;
+    let (input_pull, locals) = pull_inputs(fw.inputs())?;
     let output_store = if let Some(output) = fw.output() {
         store_outputs(&output)?
     } else {
         quote! {}
     };
+    let variadic = match fw.inputs().last() {
+        Some(arg) => arg.variadic_elem()?.is_some(),
+        None => false,
+    };
+    let input_check = if variadic {
+        quote! {
+            assert!(inputs.len() + 1 >= self.input_len(), "Expected at least {} inputs, got {}", self.input_len() - 1, inputs.len());
+        }
+    } else {
+        quote! {
+            assert_eq!(inputs.len(), self.input_len(), "Expected {} inputs, got {}", self.input_len(), inputs.len());
+        }
+    };
 
     Ok(quote! {
         fn call(&self, inputs: &ive::dyn_call::InputGetter, outputs: &mut ive::dyn_call::OutputSetter) -> ive::dyn_call::DynCallResult {
-            assert_eq!(inputs.len(), self.input_len(), "Expected {} inputs, got {}", self.input_len(), inputs.len());
+            #input_check
             assert_eq!(outputs.len(), self.output_len(), "Expected {} outputs, got {}",self.output_len(), outputs.len());
+            self.call_unchecked(inputs, outputs)
+        }
+        fn call_unchecked(&self, inputs: &ive::dyn_call::InputGetter, outputs: &mut ive::dyn_call::OutputSetter) -> ive::dyn_call::DynCallResult {
+            #(#locals)*
             let output = #fnname(#(#input_pull),*);
             #output_store
             Ok(())
@@ -363,13 +537,25 @@ fn call_dyncall(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
 }
 
 fn fn_arg_to_dynport(arg: &FnArgWrapper) -> TokenResult<TokenStream> {
-    let ty = arg.typed()?;
     let name = arg.name()?.to_string();
-    let kind = ty.type_strings(); // .tokens()?.into_iter().map(|t| t.to_string());
+    let variadic = arg.variadic_elem()?;
+    let kind = variadic
+        .unwrap_or(arg.port_type()?)
+        .to_token_stream()
+        .into_iter()
+        .map(|t| t.to_string());
+    let optional = arg.is_optional()?;
+    let owned = is_owned(variadic.unwrap_or(arg.port_type()?));
+    let variadic = variadic.is_some();
+    let ty = input_handle_type(arg)?;
     Ok(quote! {
         ive::dyn_call::DynPort {
             name: #name,
-            kind: vec![#(#kind.to_string()),*]
+            kind: vec![#(#kind.to_string()),*],
+            type_id: Some(std::any::TypeId::of::<#ty>()),
+            optional: #optional,
+            variadic: #variadic,
+            owned: #owned,
         }
     })
 }
@@ -392,6 +578,47 @@ fn outputtype_dyncall(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
     }
 }
 
+/// The output port, with the type it's stored as
+fn outputs_dyncall(fw: &FunctionWrapper) -> TokenStream {
+    match fw.output() {
+        Some(output) => {
+            let ty = output.ty;
+            let kind = output.type_strings();
+            quote! {
+                fn outputs(&self) -> Vec::<ive::dyn_call::DynPort> {
+                    vec![ive::dyn_call::DynPort {
+                        name: ive::dyn_call::OUTPUT_PORT,
+                        kind: vec![#(#kind.to_string()),*],
+                        type_id: Some(std::any::TypeId::of::<#ty>()),
+                        optional: false,
+                        variadic: false,
+                        owned: false,
+                    }]
+                }
+            }
+        }
+        None => quote! {},
+    }
+}
+
+fn format_output_dyncall(fw: &FunctionWrapper) -> TokenStream {
+    if let Some(output) = fw.output() {
+        let ty = output.ty;
+        quote! {
+            fn format_output(&self, index: usize, value: &ive::dyn_call::BoxedAny) -> Option<String> {
+                #[allow(unused_imports)]
+                use ive::dyn_call::{ViaDebug, ViaNothing};
+                match index {
+                    0 => (&ive::dyn_call::DebugProbe(value.value::<#ty>().ok()?)).probe_debug(),
+                    _ => None,
+                }
+            }
+        }
+    } else {
+        quote! {}
+    }
+}
+
 fn inputs_dyncall(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
     let input_info = fw.inputs().map(|i| fn_arg_to_dynport(&i));
     let input_info = input_info.collect::<TokenResult<Vec<_>>>()?;
@@ -410,12 +637,16 @@ fn impl_dyncall(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
     let ol_fn = output_len(fw);
     let inputs = inputs_dyncall(fw)?;
     let output_type = outputtype_dyncall(fw)?;
+    let outputs = outputs_dyncall(fw);
+    let format_output = format_output_dyncall(fw);
     Ok(quote! {
         #call
         #il_fn
         #ol_fn
         #inputs
         #output_type
+        #outputs
+        #format_output
         fn kind(&self) -> &'static str {
             #fnname
         }
@@ -426,15 +657,70 @@ fn create_dyn_wrapper(fw: &FunctionWrapper) -> TokenResult<TokenStream> {
     let dyncall_name = format_ident!("{}DynCall", fw.name().to_string().to_case(Case::Pascal));
 
     let impl_body = impl_dyncall(fw)?;
+    let fnname = fw.name().to_string();
+    let handles = port_handles(fw)?;
 
     Ok(quote! {
         pub struct #dyncall_name;
         impl ive::dyn_call::DynCall for #dyncall_name {
             #impl_body
         }
+        impl ive::ports::NodeKind for #dyncall_name {
+            const KIND: &'static str = #fnname;
+        }
+        impl #dyncall_name {
+            #(#handles)*
+        }
     })
 }
 
+/// Name of the handle for the output port, `ive::dyn_call::OUTPUT_PORT`
+const OUTPUT_HANDLE: &str = "value";
+
+/// The type a typed port handle carries for an input: what the node reads,
+/// without the reference, `Option` or slice around it.
+fn input_handle_type<'a>(arg: &FnArgWrapper<'a>) -> TokenResult<&'a syn::Type> {
+    let ty = match arg.variadic_elem()? {
+        Some(elem) => elem,
+        None => arg.port_type()?,
+    };
+    Ok(match ty {
+        syn::Type::Reference(r) => &r.elem,
+        ty => ty,
+    })
+}
+
+/// Functions returning an `ive::ports::InputPort` for each argument and an
+/// `ive::ports::OutputPort` for the output, for building graphs the
+/// compiler checks.
+fn port_handles(fw: &FunctionWrapper) -> TokenResult<Vec<TokenStream>> {
+    let mut handles = vec![];
+    for arg in fw.inputs() {
+        let name = arg.name()?;
+        let port = name.to_string();
+        let ty = input_handle_type(&arg)?;
+        let handle = match fw.output() {
+            Some(_) if name == OUTPUT_HANDLE => format_ident!("{}_input", name),
+            _ => name,
+        };
+        handles.push(quote! {
+            pub fn #handle() -> ive::ports::InputPort<Self, #ty> {
+                ive::ports::InputPort::new(#port)
+            }
+        });
+    }
+    if let Some(output) = fw.output() {
+        let handle = format_ident!("{}", OUTPUT_HANDLE);
+        let ty = output.ty;
+        handles.push(quote! {
+            pub fn #handle() -> ive::ports::OutputPort<Self, #ty> {
+                ive::ports::OutputPort::new(ive::dyn_call::OUTPUT_PORT)
+            }
+        });
+    }
+    Ok(handles)
+}
+
 const COPYABLE_TYPES: &[&str] = &[
     "std::cmp::Ordering",
     "Infallible",
@@ -0,0 +1,345 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::description::{Library, NodeDescriptor, PortDescriptor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Input => write!(f, "input"),
+            Direction::Output => write!(f, "output"),
+        }
+    }
+}
+
+/// One difference between two versions of a library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    NodeAdded {
+        node: String,
+    },
+    NodeRemoved {
+        node: String,
+    },
+    PortAdded {
+        node: String,
+        direction: Direction,
+        port: String,
+    },
+    PortRemoved {
+        node: String,
+        direction: Direction,
+        port: String,
+    },
+    PortRenamed {
+        node: String,
+        direction: Direction,
+        from: String,
+        to: String,
+    },
+    TypeChanged {
+        node: String,
+        direction: Direction,
+        port: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Change {
+    /// Whether saved graphs using the old library could stop working.
+    /// Everything is breaking except new nodes and new outputs: graphs
+    /// connect to ports by name, and a new input needs a connection.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(
+            self,
+            Change::NodeAdded { .. }
+                | Change::PortAdded {
+                    direction: Direction::Output,
+                    ..
+                }
+        )
+    }
+
+    /// The node the change is to
+    pub fn node(&self) -> &str {
+        match self {
+            Change::NodeAdded { node }
+            | Change::NodeRemoved { node }
+            | Change::PortAdded { node, .. }
+            | Change::PortRemoved { node, .. }
+            | Change::PortRenamed { node, .. }
+            | Change::TypeChanged { node, .. } => node,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::NodeAdded { node } => write!(f, "added node {}", node),
+            Change::NodeRemoved { node } => write!(f, "removed node {}", node),
+            Change::PortAdded {
+                node,
+                direction,
+                port,
+            } => write!(f, "added {} {}.{}", direction, node, port),
+            Change::PortRemoved {
+                node,
+                direction,
+                port,
+            } => write!(f, "removed {} {}.{}", direction, node, port),
+            Change::PortRenamed {
+                node,
+                direction,
+                from,
+                to,
+            } => write!(f, "renamed {} {}.{} to {}", direction, node, from, to),
+            Change::TypeChanged {
+                node,
+                direction,
+                port,
+                from,
+                to,
+            } => write!(
+                f,
+                "changed type of {} {}.{} from {} to {}",
+                direction, node, port, from, to
+            ),
+        }
+    }
+}
+
+/// The changes from one version of a library to the next.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryDiff {
+    pub changes: Vec<Change>,
+}
+
+impl LibraryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(Change::is_breaking)
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|c| c.is_breaking())
+    }
+}
+
+/// Compares two versions of a library, matching nodes by name and ports by
+/// id.  A port that disappears and one that appears in the same place with
+/// the same type count as a rename.
+pub fn diff_libraries(old: &Library, new: &Library) -> LibraryDiff {
+    let mut changes = vec![];
+    for old_node in old.nodes() {
+        match new.nodes().iter().find(|n| n.name() == old_node.name()) {
+            Some(new_node) => diff_nodes(old_node, new_node, &mut changes),
+            None => changes.push(Change::NodeRemoved {
+                node: old_node.name().to_string(),
+            }),
+        }
+    }
+    for new_node in new.nodes() {
+        if !old.nodes().iter().any(|n| n.name() == new_node.name()) {
+            changes.push(Change::NodeAdded {
+                node: new_node.name().to_string(),
+            });
+        }
+    }
+    LibraryDiff { changes }
+}
+
+fn diff_nodes(old: &NodeDescriptor, new: &NodeDescriptor, changes: &mut Vec<Change>) {
+    diff_ports(
+        old.name(),
+        Direction::Input,
+        old.inputs(),
+        new.inputs(),
+        changes,
+    );
+    diff_ports(
+        old.name(),
+        Direction::Output,
+        old.outputs(),
+        new.outputs(),
+        changes,
+    );
+}
+
+fn diff_ports(
+    node: &str,
+    direction: Direction,
+    old: &[PortDescriptor],
+    new: &[PortDescriptor],
+    changes: &mut Vec<Change>,
+) {
+    let node = node.to_string();
+    let find = |ports: &[PortDescriptor], id: &str| ports.iter().position(|p| p.id() == id);
+    let added = new
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| find(old, p.id()).is_none())
+        .collect::<Vec<_>>();
+
+    for (i, old_port) in old.iter().enumerate() {
+        let new_port = match find(new, old_port.id()) {
+            Some(j) => &new[j],
+            None => {
+                let renamed = added
+                    .iter()
+                    .find(|(j, p)| *j == i && p.ty() == old_port.ty());
+                changes.push(match renamed {
+                    Some((_, p)) => Change::PortRenamed {
+                        node: node.clone(),
+                        direction,
+                        from: old_port.name().to_string(),
+                        to: p.name().to_string(),
+                    },
+                    None => Change::PortRemoved {
+                        node: node.clone(),
+                        direction,
+                        port: old_port.name().to_string(),
+                    },
+                });
+                continue;
+            }
+        };
+        if new_port.name() != old_port.name() {
+            changes.push(Change::PortRenamed {
+                node: node.clone(),
+                direction,
+                from: old_port.name().to_string(),
+                to: new_port.name().to_string(),
+            });
+        }
+        if new_port.ty() != old_port.ty() {
+            changes.push(Change::TypeChanged {
+                node: node.clone(),
+                direction,
+                port: new_port.name().to_string(),
+                from: old_port.ty().to_string(),
+                to: new_port.ty().to_string(),
+            });
+        }
+    }
+
+    for (j, port) in added {
+        let renamed = old
+            .get(j)
+            .is_some_and(|o| find(new, o.id()).is_none() && o.ty() == port.ty());
+        if !renamed {
+            changes.push(Change::PortAdded {
+                node: node.clone(),
+                direction,
+                port: port.name().to_string(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(nodes: Vec<NodeDescriptor>) -> Library {
+        let mut lib = Library::new("nodes", "nodes", "");
+        for node in nodes {
+            lib.add_node(node);
+        }
+        lib
+    }
+
+    fn add(inputs: &[(&str, &str)], output: &str) -> NodeDescriptor {
+        let inputs = inputs
+            .iter()
+            .map(|(name, ty)| PortDescriptor::new(name, "", ty))
+            .collect();
+        let outputs = vec![PortDescriptor::new("output", "", output)];
+        NodeDescriptor::new("add", "", inputs, outputs)
+    }
+
+    fn three() -> NodeDescriptor {
+        NodeDescriptor::new("three", "", vec![], vec![])
+    }
+
+    fn four() -> NodeDescriptor {
+        NodeDescriptor::new("four", "", vec![], vec![])
+    }
+
+    #[test]
+    fn test_nodes() {
+        let old = library(vec![three()]);
+        let new = library(vec![four()]);
+        let diff = diff_libraries(&old, &new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::NodeRemoved {
+                    node: "three".to_string()
+                },
+                Change::NodeAdded {
+                    node: "four".to_string()
+                },
+            ]
+        );
+        assert!(diff.is_breaking());
+        assert_eq!(diff.breaking().count(), 1);
+        assert!(!diff_libraries(&new, &library(vec![four(), three()])).is_breaking());
+        assert!(diff_libraries(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_ports() {
+        let old = library(vec![add(&[("left", "u32"), ("right", "u32")], "u32")]);
+        let new = library(vec![add(
+            &[("a", "u32"), ("right", "u64"), ("carry", "bool")],
+            "u32",
+        )]);
+        let changes = diff_libraries(&old, &new)
+            .changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                "renamed input add.left to a",
+                "changed type of input add.right from u32 to u64",
+                "added input add.carry",
+            ]
+        );
+
+        // A new type in the same place isn't a rename
+        let new = library(vec![add(&[("a", "i32"), ("right", "u32")], "u32")]);
+        let changes = diff_libraries(&old, &new)
+            .changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(changes, vec!["removed input add.left", "added input add.a"]);
+    }
+
+    #[test]
+    fn test_breaking() {
+        let port = |direction| Change::PortAdded {
+            node: "add".to_string(),
+            direction,
+            port: "p".to_string(),
+        };
+        assert!(port(Direction::Input).is_breaking());
+        assert!(!port(Direction::Output).is_breaking());
+    }
+}
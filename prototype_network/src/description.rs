@@ -21,6 +21,19 @@ impl Library {
     pub fn add_node(&mut self, node: NodeDescriptor) {
         self.nodes.push(node);
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn nodes(&self) -> &[NodeDescriptor] {
+        &self.nodes
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -47,6 +60,19 @@ impl NodeDescriptor {
             outputs,
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn inputs(&self) -> &[PortDescriptor] {
+        &self.inputs
+    }
+    pub fn outputs(&self) -> &[PortDescriptor] {
+        &self.outputs
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -55,6 +81,9 @@ pub struct PortDescriptor {
     name: String,
     description: String,
     ty: String,
+    /// The node runs with nothing connected to this input
+    #[serde(default)]
+    optional: bool,
 }
 
 impl PortDescriptor {
@@ -64,6 +93,28 @@ impl PortDescriptor {
             name: name.to_string(),
             description: description.to_string(),
             ty: ty.to_string(),
+            optional: false,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+}
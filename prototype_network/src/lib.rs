@@ -1 +1,3 @@
+pub mod compat;
 pub mod description;
+pub mod render;
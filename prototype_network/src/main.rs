@@ -1,4 +1,5 @@
 use prototype_network::description::{Library, NodeDescriptor, PortDescriptor};
+use prototype_network::render::library_to_markdown;
 
 fn main() {
     let mut lib = Library::new("sample_nodes", "sample_nodes", "Sample nodes");
@@ -33,6 +34,11 @@ fn main() {
         vec![PortDescriptor::new("output", "Output", "u32")],
     ));
 
+    if std::env::args().any(|a| a == "--markdown") {
+        print!("{}", library_to_markdown(&lib));
+        return;
+    }
+
     let lib_json = serde_json::to_string_pretty(&lib).unwrap();
     println!("{}", lib_json);
 }
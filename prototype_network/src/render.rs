@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use crate::description::{Library, NodeDescriptor, PortDescriptor};
+
+/// Escapes text for a Markdown table cell.
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('`', "\\`").replace('\n', " ")
+}
+
+/// Escapes text for a Markdown table cell as a code span.  Backslashes don't
+/// escape inside code spans, so the fence is made longer than any run of
+/// backticks in the text.
+fn code_cell(s: &str) -> String {
+    let text = s.replace('|', "\\|").replace('\n', " ");
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    match longest {
+        0 => format!("{}{}{}", fence, text, fence),
+        _ => format!("{} {} {}", fence, text, fence),
+    }
+}
+
+fn port_table(out: &mut String, title: &str, ports: &[PortDescriptor]) {
+    _ = writeln!(out, "**{}**", title);
+    _ = writeln!(out);
+    if ports.is_empty() {
+        _ = writeln!(out, "None");
+        _ = writeln!(out);
+        return;
+    }
+    _ = writeln!(out, "| Name | Type | Description |");
+    _ = writeln!(out, "| --- | --- | --- |");
+    for port in ports {
+        _ = writeln!(
+            out,
+            "| {} | {} | {} |",
+            cell(port.name()),
+            code_cell(port.ty()),
+            cell(port.description())
+        );
+    }
+    _ = writeln!(out);
+}
+
+/// Renders one node as a Markdown section.
+pub fn node_to_markdown(node: &NodeDescriptor) -> String {
+    let mut out = String::new();
+    _ = writeln!(out, "## {}", node.name());
+    _ = writeln!(out);
+    if !node.description().is_empty() {
+        _ = writeln!(out, "{}", node.description());
+        _ = writeln!(out);
+    }
+    port_table(&mut out, "Inputs", node.inputs());
+    port_table(&mut out, "Outputs", node.outputs());
+    out
+}
+
+/// Renders a library as a Markdown catalogue: a heading with the library
+/// description, an index of nodes, then a section per node with its ports.
+pub fn library_to_markdown(library: &Library) -> String {
+    let mut out = String::new();
+    _ = writeln!(out, "# {}", library.name());
+    _ = writeln!(out);
+    if !library.description().is_empty() {
+        _ = writeln!(out, "{}", library.description());
+        _ = writeln!(out);
+    }
+    _ = writeln!(out, "Crate: `{}`", library.crate_name());
+    _ = writeln!(out);
+    for node in library.nodes() {
+        _ = writeln!(out, "- [{}](#{})", node.name(), anchor(node.name()));
+    }
+    if !library.nodes().is_empty() {
+        _ = writeln!(out);
+    }
+    for node in library.nodes() {
+        out += &node_to_markdown(node);
+    }
+    out
+}
+
+/// The anchor GitHub style Markdown renderers generate for a heading.
+fn anchor(heading: &str) -> String {
+    heading
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Escapes text for a quoted Mermaid label.  Labels can hold HTML, so
+/// angle brackets are escaped as well as quotes.
+pub fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+/// Renders a node as a Mermaid flowchart with its inputs flowing into it and
+/// its outputs flowing out, labeled with their types.
+pub fn node_to_mermaid(node: &NodeDescriptor) -> String {
+    let mut out = String::new();
+    _ = writeln!(out, "flowchart LR");
+    _ = writeln!(out, "    node[\"{}\"]", mermaid_escape(node.name()));
+    for (i, port) in node.inputs().iter().enumerate() {
+        _ = writeln!(
+            out,
+            "    in{}([\"{}\"]) -->|\"{}\"| node",
+            i,
+            mermaid_escape(port.name()),
+            mermaid_escape(port.ty())
+        );
+    }
+    for (i, port) in node.outputs().iter().enumerate() {
+        _ = writeln!(
+            out,
+            "    node -->|\"{}\"| out{}([\"{}\"])",
+            mermaid_escape(port.ty()),
+            i,
+            mermaid_escape(port.name())
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_library() -> Library {
+        let mut lib = Library::new("sample_nodes", "sample_nodes", "Sample nodes");
+        lib.add_node(NodeDescriptor::new(
+            "add_u32",
+            "Add two u32 numbers together",
+            vec![
+                PortDescriptor::new("left", "Left input", "u32"),
+                PortDescriptor::new("right", "Right | input", "u32"),
+            ],
+            vec![PortDescriptor::new("output", "Output", "u32")],
+        ));
+        lib.add_node(NodeDescriptor::new(
+            "three",
+            "",
+            vec![],
+            vec![PortDescriptor::new("output", "Output", "Vec<u32>")],
+        ));
+        lib
+    }
+
+    #[test]
+    fn test_library_to_markdown() {
+        let md = library_to_markdown(&test_library());
+        assert!(md.starts_with("# sample_nodes\n\nSample nodes\n\nCrate: `sample_nodes`\n"));
+        assert!(md.contains("- [add_u32](#add_u32)\n- [three](#three)\n"));
+        assert!(md.contains("## add_u32\n\nAdd two u32 numbers together\n\n**Inputs**\n"));
+        assert!(md.contains("| right | `u32` | Right \\| input |\n"));
+        assert!(md.contains("## three\n\n**Inputs**\n\nNone\n"));
+        assert!(md.contains("| output | `Vec<u32>` | Output |\n"));
+    }
+
+    #[test]
+    fn test_markdown_escaping() {
+        let mut lib = Library::new("lib", "lib", "");
+        lib.add_node(NodeDescriptor::new(
+            "quote",
+            "",
+            vec![PortDescriptor::new("a|b", "Uses `code`", "T<`a`>")],
+            vec![PortDescriptor::new("out", "", "Or<A | B>")],
+        ));
+        let md = library_to_markdown(&lib);
+        assert!(md.contains("| a\\|b | `` T<`a`> `` | Uses \\`code\\` |\n"));
+        assert!(md.contains("| out | `Or<A \\| B>` |  |\n"));
+    }
+
+    #[test]
+    fn test_mermaid_escape() {
+        assert_eq!(mermaid_escape("Vec<\"a\">"), "Vec#lt;#quot;a#quot;#gt;");
+    }
+
+    #[test]
+    fn test_node_to_mermaid() {
+        let lib = test_library();
+        let mermaid = node_to_mermaid(&lib.nodes()[0]);
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n    node[\"add_u32\"]\n    in0([\"left\"]) -->|\"u32\"| node\n    in1([\"right\"]) -->|\"u32\"| node\n    node -->|\"u32\"| out0([\"output\"])\n"
+        );
+    }
+}
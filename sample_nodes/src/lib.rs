@@ -1,15 +1,28 @@
+/// Add two u32 numbers together
+///
+/// # Arguments
+///
+/// * `left` - Left input
+/// * `right` - Right input
 pub fn add(left: u32, right: u32) -> u32 {
     left + right
 }
 
+/// Return the number 3
 pub fn three() -> u32 {
     3
 }
 
+/// Return the number 4
 pub fn four() -> u32 {
     4
 }
 
+/// Copy a u32 value
+///
+/// # Arguments
+///
+/// * `input` - Input
 pub fn copy_u32(input: u32) -> u32 {
     input
 }